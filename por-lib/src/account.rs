@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::decimal::Decimal;
+use crate::liability::PathStep;
+use crate::merkle::{build_levels, proof_path, root_from_path, SumNode};
+
+/// One attested account: its data source, id and balance per asset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountEntry {
+    /// Key of the data source, e.g. `binanceSpot`, or `onchain:<chain>`.
+    pub source: String,
+    /// The id the source dedupes its accounts by, e.g. the uid; `#<request index>` for an account
+    /// identified by a request of no id, e.g. the UM positions of a unified account holding none.
+    pub account_id: String,
+    pub balances: BTreeMap<String, Decimal>,
+}
+
+impl AccountEntry {
+    /// `H(salt || source || 0x00 || account_id)`: opening one account reveals no other salt.
+    pub fn account_salt(&self, salt: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(salt);
+        hasher.update(self.source.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(self.account_id.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// `H(account_salt || JSON of the entry)`, the entry JSON being canonical
+    /// (fields in declaration order, sorted assets, normalized balances).
+    pub fn commitment(&self, account_salt: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(account_salt);
+        hasher.update(serde_json::to_vec(self).unwrap());
+        hasher.finalize().into()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AccountError {
+    #[error("invalid opening: {0}")]
+    InvalidOpening(String),
+    #[error("root mismatch: computed {computed}, committed {committed}")]
+    RootMismatch { computed: String, committed: String },
+}
+
+/// The Merkle tree of the salted account commitments.
+///
+/// Leaves are `SumNode::leaf(commitment, [])` in attestation order, so the tree carries no sums.
+pub struct AccountTree {
+    entries: Vec<AccountEntry>,
+    salts: Vec<[u8; 32]>,
+    levels: Vec<Vec<SumNode>>,
+}
+
+impl AccountTree {
+    pub fn build(entries: Vec<AccountEntry>, salt: &[u8]) -> Self {
+        let salts: Vec<[u8; 32]> = entries.iter().map(|e| e.account_salt(salt)).collect();
+        let leaves = entries
+            .iter()
+            .zip(&salts)
+            .map(|(e, s)| SumNode::leaf(&e.commitment(s), &[]))
+            .collect();
+        // Without sums nothing can overflow
+        let levels = build_levels(leaves, 0).unwrap();
+        Self { entries, salts, levels }
+    }
+
+    /// Hex of the root hash, as committed in the public values.
+    pub fn root_hex(&self) -> String {
+        hex::encode(self.levels[self.levels.len() - 1][0].hash)
+    }
+
+    pub fn account_count(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// The opening of the account at `index`, `None` out of range.
+    pub fn opening(&self, index: usize) -> Option<AccountOpening> {
+        let entry = self.entries.get(index)?.clone();
+        let path = proof_path(&self.levels, index)
+            .into_iter()
+            .map(|(node, left)| PathStep {
+                hash: hex::encode(node.hash),
+                sums: vec![],
+                left,
+            })
+            .collect();
+
+        Some(AccountOpening {
+            entry,
+            account_salt: hex::encode(self.salts[index]),
+            path,
+            root: self.root_hex(),
+        })
+    }
+}
+
+/// What is handed to an auditor: one account, its salt and the path to the committed account root.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOpening {
+    pub entry: AccountEntry,
+    pub account_salt: String,
+    pub path: Vec<PathStep>,
+    pub root: String,
+}
+
+fn decode_hash(hash: &str) -> Result<[u8; 32], AccountError> {
+    hex::decode(hash)
+        .ok()
+        .and_then(|h| h.try_into().ok())
+        .ok_or_else(|| AccountError::InvalidOpening(format!("invalid hash: {}", hash)))
+}
+
+impl AccountOpening {
+    /// Recomputes the root from the account's commitment and checks it against `committed_root`.
+    pub fn verify(&self, committed_root: &str) -> Result<(), AccountError> {
+        if self.path.iter().any(|step| !step.sums.is_empty()) {
+            return Err(AccountError::InvalidOpening("unexpected sums".to_string()));
+        }
+        let account_salt = decode_hash(&self.account_salt)?;
+        let leaf = SumNode::leaf(&self.entry.commitment(&account_salt), &[]);
+        let path = self
+            .path
+            .iter()
+            .map(|step| {
+                let hash = decode_hash(&step.hash)?;
+                Ok((SumNode { hash, sums: vec![] }, step.left))
+            })
+            .collect::<Result<Vec<_>, AccountError>>()?;
+        let root = root_from_path(leaf, &path).unwrap();
+
+        let computed = hex::encode(root.hash);
+        if !computed.eq_ignore_ascii_case(committed_root) {
+            return Err(AccountError::RootMismatch {
+                computed,
+                committed: committed_root.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn entries() -> Vec<AccountEntry> {
+        [
+            ("binanceSpot", "1001", "0.5"),
+            ("okxTrading", "2002", "1.25"),
+            ("onchain:ethereum", "0xab", "3"),
+        ]
+        .iter()
+        .map(|(source, id, bal)| AccountEntry {
+            source: source.to_string(),
+            account_id: id.to_string(),
+            balances: BTreeMap::from([("BTC".to_string(), bal.parse().unwrap())]),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn every_opening_verifies() {
+        let tree = AccountTree::build(entries(), SALT);
+        assert_eq!(tree.account_count(), 3);
+        for index in 0..3 {
+            let opening = tree.opening(index).unwrap();
+            let opening: AccountOpening = serde_json::from_str(&serde_json::to_string(&opening).unwrap()).unwrap();
+            assert_eq!(opening.entry, entries()[index]);
+            assert!(opening.verify(&tree.root_hex()).is_ok());
+        }
+        assert!(tree.opening(3).is_none());
+    }
+
+    #[test]
+    fn root_depends_on_salt_and_balances() {
+        let root = AccountTree::build(entries(), SALT).root_hex();
+        assert_ne!(AccountTree::build(entries(), b"another salt").root_hex(), root);
+        let mut changed = entries();
+        changed[1].balances.insert("BTC".to_string(), "1.26".parse().unwrap());
+        assert_ne!(AccountTree::build(changed, SALT).root_hex(), root);
+    }
+
+    #[test]
+    fn account_salts_differ() {
+        let entries = entries();
+        assert_ne!(entries[0].account_salt(SALT), entries[1].account_salt(SALT));
+    }
+
+    #[test]
+    fn rejects_tampered_openings() {
+        let tree = AccountTree::build(entries(), SALT);
+        let mut opening = tree.opening(1).unwrap();
+        opening.entry.balances.insert("BTC".to_string(), "2".parse().unwrap());
+        assert!(matches!(
+            opening.verify(&tree.root_hex()),
+            Err(AccountError::RootMismatch { .. })
+        ));
+
+        let mut opening = tree.opening(1).unwrap();
+        opening.account_salt = hex::encode(entries()[0].account_salt(SALT));
+        assert!(matches!(
+            opening.verify(&tree.root_hex()),
+            Err(AccountError::RootMismatch { .. })
+        ));
+
+        let mut opening = tree.opening(1).unwrap();
+        opening.path[0].sums.push(Decimal::ZERO);
+        assert!(matches!(
+            opening.verify(&tree.root_hex()),
+            Err(AccountError::InvalidOpening(_))
+        ));
+    }
+}
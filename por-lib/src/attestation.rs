@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AttestationError {
+    #[error("timestamp in the future: {0}")]
+    TimestampInFuture(String),
+    #[error("attestation too old: {0}")]
+    AttestationTooOld(String),
+    #[error("timestamp spread too large: {0}")]
+    TimestampSpreadTooLarge(String),
+    #[error("unknown attestor: {0}")]
+    UnknownAttestor(String),
+    #[error("insufficient attestors: {0}")]
+    InsufficientAttestors(String),
+}
+
+/// Bounds on the request timestamps of the attestations, in ms.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FreshnessConfig {
+    /// Host time the attestations are checked against, ms since epoch. No request may be later.
+    pub reference_time: u128,
+    /// Max age of the earliest request at `reference_time`.
+    pub max_age: Option<u128>,
+    /// Max time between the earliest and the latest request.
+    pub max_spread: Option<u128>,
+}
+
+/// Checks the requests of all attestations, `(earliest, latest)` request of each, against the freshness
+/// bounds, if any.
+pub fn check_freshness(times: &[(u128, u128)], freshness: &Option<FreshnessConfig>) -> Result<(), AttestationError> {
+    let Some(freshness) = freshness else {
+        return Ok(());
+    };
+    if times.is_empty() {
+        return Ok(());
+    }
+    let earliest = times.iter().map(|t| t.0).min().unwrap();
+    let latest = times.iter().map(|t| t.1).max().unwrap();
+
+    if latest > freshness.reference_time {
+        return Err(AttestationError::TimestampInFuture(format!(
+            "{} after {}",
+            latest, freshness.reference_time
+        )));
+    }
+    if let Some(max_age) = freshness.max_age {
+        if freshness.reference_time - earliest > max_age {
+            return Err(AttestationError::AttestationTooOld(format!(
+                "{} older than {} at {}",
+                earliest, max_age, freshness.reference_time
+            )));
+        }
+    }
+    if let Some(max_spread) = freshness.max_spread {
+        if latest - earliest > max_spread {
+            return Err(AttestationError::TimestampSpreadTooLarge(format!(
+                "{}..{} wider than {}",
+                earliest, latest, max_spread
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// What the m-of-n check looks at of one `public_data` entry.
+pub struct AttestedEntry<'a> {
+    pub task_id: &'a str,
+    pub report_tx_hash: &'a str,
+    pub attestor: &'a str,
+    pub requests: Vec<(&'a str, &'a str)>, // (url, body)
+    pub data: &'a str,
+}
+
+/// Whether `attestor` is in `allowlist`, case-insensitive. An empty allowlist trusts any attestor.
+pub fn is_trusted_attestor(allowlist: &[String], attestor: &str) -> bool {
+    allowlist.is_empty() || allowlist.iter().any(|a| a.eq_ignore_ascii_case(attestor))
+}
+
+// m-of-n: the responses are extracted from the first entry, which `min_attestors` distinct
+// attestors must agree with on the requests and the attested data.
+pub fn check_attestors(
+    entries: &[AttestedEntry],
+    allowlist: &[String],
+    min_attestors: usize,
+) -> Result<(), AttestationError> {
+    if entries.is_empty() {
+        return Err(AttestationError::InsufficientAttestors("no attestation".to_string()));
+    }
+    // Without an allowlist any key counts, even one of the prover's own
+    if min_attestors > 1 && allowlist.is_empty() {
+        return Err(AttestationError::InsufficientAttestors(format!(
+            "{} attestors required without an allowlist",
+            min_attestors
+        )));
+    }
+
+    let mut agreeing = HashSet::new();
+    for entry in entries {
+        if !is_trusted_attestor(allowlist, entry.attestor) {
+            return Err(AttestationError::UnknownAttestor(entry.attestor.to_string()));
+        }
+        if entry.requests == entries[0].requests && entry.data == entries[0].data {
+            agreeing.insert(entry.attestor.to_ascii_lowercase());
+        }
+    }
+    if agreeing.len() < min_attestors {
+        return Err(AttestationError::InsufficientAttestors(format!(
+            "{} of {} attestors agree, {} required",
+            agreeing.len(),
+            entries.len(),
+            min_attestors
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn freshness(max_age: Option<u128>, max_spread: Option<u128>) -> Option<FreshnessConfig> {
+        Some(FreshnessConfig {
+            reference_time: 10_000,
+            max_age,
+            max_spread,
+        })
+    }
+
+    #[test]
+    fn accepts_requests_within_the_bounds() {
+        let times = [(9_000, 9_500), (9_200, 10_000)];
+        assert!(check_freshness(&times, &freshness(Some(1_000), Some(1_000))).is_ok());
+        assert!(check_freshness(&times, &freshness(None, None)).is_ok());
+        assert!(check_freshness(&[], &freshness(Some(0), Some(0))).is_ok());
+        // unchecked without bounds
+        assert!(check_freshness(&[(0, u128::MAX)], &None).is_ok());
+    }
+
+    #[test]
+    fn rejects_requests_after_the_reference_time() {
+        for bounds in [freshness(None, None), freshness(Some(1_000), Some(1_000))] {
+            assert!(matches!(
+                check_freshness(&[(9_000, 10_001)], &bounds),
+                Err(AttestationError::TimestampInFuture(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_old_requests() {
+        assert!(matches!(
+            check_freshness(&[(8_999, 9_500)], &freshness(Some(1_000), None)),
+            Err(AttestationError::AttestationTooOld(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_a_wide_spread() {
+        let times = [(9_000, 9_000), (9_500, 9_501)];
+        assert!(check_freshness(&times, &freshness(None, Some(501))).is_ok());
+        assert!(matches!(
+            check_freshness(&times, &freshness(None, Some(500))),
+            Err(AttestationError::TimestampSpreadTooLarge(_))
+        ));
+    }
+
+    fn entry<'a>(attestor: &'a str, data: &'a str) -> AttestedEntry<'a> {
+        AttestedEntry {
+            task_id: "task",
+            report_tx_hash: "tx",
+            attestor,
+            requests: vec![("https://api.binance.com/api/v3/account", "")],
+            data,
+        }
+    }
+
+    fn allowlist() -> Vec<String> {
+        vec!["0xAA".to_string(), "0xbb".to_string(), "0xcc".to_string()]
+    }
+
+    #[test]
+    fn accepts_enough_agreeing_attestors() {
+        let entries = [entry("0xaa", "d"), entry("0xBB", "d"), entry("0xcc", "other")];
+        assert!(check_attestors(&entries, &allowlist(), 2).is_ok());
+        assert!(check_attestors(&entries[..1], &[], 1).is_ok());
+    }
+
+    #[test]
+    fn counts_agreeing_attestors_once() {
+        let entries = [entry("0xaa", "d"), entry("0xAA", "d"), entry("0xcc", "other")];
+        assert!(matches!(
+            check_attestors(&entries, &allowlist(), 2),
+            Err(AttestationError::InsufficientAttestors(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_attestors_off_the_allowlist() {
+        let entries = [entry("0xaa", "d"), entry("0xdd", "d")];
+        assert!(matches!(
+            check_attestors(&entries, &allowlist(), 1),
+            Err(AttestationError::UnknownAttestor(_))
+        ));
+    }
+
+    #[test]
+    fn requires_an_allowlist_for_more_than_one_attestor() {
+        let entries = [entry("0xaa", "d"), entry("0xbb", "d")];
+        assert!(check_attestors(&entries, &[], 1).is_ok());
+        assert!(matches!(
+            check_attestors(&entries, &[], 2),
+            Err(AttestationError::InsufficientAttestors(_))
+        ));
+        assert!(matches!(
+            check_attestors(&[], &allowlist(), 1),
+            Err(AttestationError::InsufficientAttestors(_))
+        ));
+    }
+}
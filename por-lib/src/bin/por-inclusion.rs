@@ -0,0 +1,227 @@
+//! Per-user inclusion proofs against the liability root committed by the zkVM program,
+//! and openings of single attested accounts against the committed account root.
+//!
+//! ```sh
+//! por-inclusion prove <liabilities.json> <user-hash>
+//! por-inclusion prove-all <liabilities.json> <out-dir>
+//! por-inclusion verify <receipt.json> <public-values.json>
+//! por-inclusion accounts <responses.json> <config.json>
+//! por-inclusion open-account <accounts.json> <salt> <index>
+//! por-inclusion verify-account <opening.json> <public-values.json>
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use por_lib::account::{AccountEntry, AccountOpening, AccountTree};
+use por_lib::decimal::Decimal;
+use por_lib::liability::{InclusionProof, LiabilityEntry, LiabilityTree};
+use por_lib::sources::onchain::{extract_chain, OnchainConfig};
+use por_lib::sources::{extract, AttestedRequest, BalanceParseMode, REGISTRY, VENUES};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::{env, fs};
+
+/// The fields of the `liability` or `solvency` public values a receipt is checked against.
+#[derive(Deserialize)]
+struct CommittedLiability {
+    kind: String,
+    status: i16,
+    liability_root: String,
+    #[serde(default)]
+    liability_balance: BTreeMap<String, Decimal>,
+    #[serde(default)]
+    solvency: BTreeMap<String, IgnoredAny>,
+}
+
+/// The fields of the `asset-balance` public values an opening is checked against.
+#[derive(Deserialize)]
+struct CommittedAccounts {
+    kind: String,
+    status: i16,
+    account_root: String,
+}
+
+/// The options of the program config the accounts are read with.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SourcesConfig {
+    #[serde(default)]
+    balance_parse_mode: BalanceParseMode,
+    #[serde(default)]
+    onchain: Vec<OnchainConfig>,
+}
+
+/// A request of the run and the response it was attested with.
+#[derive(Deserialize)]
+struct RecordedRequest {
+    url: String,
+    #[serde(default)]
+    body: String,
+    response: Value,
+}
+
+/// Fails unless the public values are those of a successful run of one of `kinds`.
+fn check_run(kind: &str, status: i16, kinds: &[&str]) -> Result<()> {
+    if !kinds.contains(&kind) {
+        bail!("public values of kind {}, expected {}", kind, kinds.join(" or "));
+    }
+    if status != 0 {
+        bail!("public values of a failed run, status {}", status);
+    }
+    Ok(())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> Result<T> {
+    let data = fs::read_to_string(path).with_context(|| format!("read {}", path))?;
+    serde_json::from_str(&data).with_context(|| format!("parse {}", path))
+}
+
+fn build_tree(path: &str) -> Result<LiabilityTree> {
+    let entries: Vec<LiabilityEntry> = read_json(path)?;
+    Ok(LiabilityTree::build(&entries)?)
+}
+
+fn prove(liabilities: &str, user_hash: &str) -> Result<()> {
+    let tree = build_tree(liabilities)?;
+    let proof = tree
+        .inclusion_proof(user_hash)
+        .ok_or_else(|| anyhow!("user {} not found", user_hash))?;
+    println!("{}", serde_json::to_string_pretty(&proof)?);
+    Ok(())
+}
+
+fn prove_all(liabilities: &str, out_dir: &str) -> Result<()> {
+    let entries: Vec<LiabilityEntry> = read_json(liabilities)?;
+    let tree = LiabilityTree::build(&entries)?;
+    fs::create_dir_all(out_dir)?;
+    for entry in &entries {
+        let proof = tree.inclusion_proof(&entry.user_hash).unwrap();
+        let path = format!("{}/{}.json", out_dir, proof.user_hash);
+        fs::write(&path, serde_json::to_string_pretty(&proof)?)?;
+    }
+    println!("root: {}", tree.root_hex());
+    println!("{} receipts written to {}", entries.len(), out_dir);
+    Ok(())
+}
+
+fn verify(receipt: &str, public_values: &str) -> Result<()> {
+    let proof: InclusionProof = read_json(receipt)?;
+    let committed: CommittedLiability = read_json(public_values)?;
+    check_run(&committed.kind, committed.status, &["liability", "solvency"])?;
+
+    let totals = proof.verify(&committed.liability_root)?;
+    // The assets of the tree, with which `liability_balance` or `solvency` are keyed
+    let committed_assets: Vec<&String> = match committed.kind.as_str() {
+        "liability" => committed.liability_balance.keys().collect(),
+        _ => committed.solvency.keys().collect(),
+    };
+    if !proof.assets.iter().eq(committed_assets) {
+        bail!("receipt assets do not match the committed assets");
+    }
+    // Only the `liability` kind commits the totals, `solvency` may not reveal them.
+    if !committed.liability_balance.is_empty() && committed.liability_balance != totals {
+        bail!("totals do not match the committed liability balance");
+    }
+
+    println!("OK: {} is included in {}", proof.user_hash, committed.liability_root);
+    for (asset, bal) in proof.assets.iter().zip(&proof.balances) {
+        println!("  {}: {}", asset, bal);
+    }
+    Ok(())
+}
+
+/// Rebuilds the accounts of a run, as the program reads them, from the responses of its attested requests.
+fn accounts(responses: &str, config: &str) -> Result<()> {
+    let responses: HashMap<String, Vec<RecordedRequest>> = read_json(responses)?;
+    let config: SourcesConfig = read_json(config)?;
+    let requests = |key: &str| {
+        responses.get(key).map(|requests| {
+            requests
+                .iter()
+                .map(|r| AttestedRequest {
+                    url: &r.url,
+                    body: &r.body,
+                    response: r.response.clone(),
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // In the order the program verifies the sources
+    let mut accounts = vec![];
+    for venue in VENUES {
+        for source in REGISTRY.iter().filter(|s| s.venue() == *venue) {
+            if let Some(requests) = requests(source.key()) {
+                extract(
+                    *source,
+                    &requests,
+                    0,
+                    config.balance_parse_mode,
+                    &mut BTreeMap::new(),
+                    Some(&mut accounts),
+                )
+                .with_context(|| source.key())?;
+            }
+        }
+    }
+    for chain in &config.onchain {
+        if let Some(requests) = requests(&chain.key()) {
+            extract_chain(chain, &requests, &mut accounts).with_context(|| chain.key())?;
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&accounts)?);
+    Ok(())
+}
+
+fn open_account(accounts: &str, salt: &str, index: &str) -> Result<()> {
+    let entries: Vec<AccountEntry> = read_json(accounts)?;
+    let tree = AccountTree::build(entries, salt.as_bytes());
+    let index: usize = index.parse().context("parse index")?;
+    let opening = tree
+        .opening(index)
+        .ok_or_else(|| anyhow!("account {} not found", index))?;
+    println!("{}", serde_json::to_string_pretty(&opening)?);
+    Ok(())
+}
+
+fn verify_account(opening: &str, public_values: &str) -> Result<()> {
+    let opening: AccountOpening = read_json(opening)?;
+    let committed: CommittedAccounts = read_json(public_values)?;
+    check_run(&committed.kind, committed.status, &["asset-balance"])?;
+    if committed.account_root.is_empty() {
+        bail!("public values of a run without accountSalt commit no accounts");
+    }
+
+    opening.verify(&committed.account_root)?;
+
+    let entry = &opening.entry;
+    println!(
+        "OK: {} {} is included in {}",
+        entry.source, entry.account_id, committed.account_root
+    );
+    for (asset, bal) in &entry.balances {
+        println!("  {}: {}", asset, bal);
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["prove", liabilities, user_hash] => prove(liabilities, user_hash),
+        ["prove-all", liabilities, out_dir] => prove_all(liabilities, out_dir),
+        ["verify", receipt, public_values] => verify(receipt, public_values),
+        ["accounts", responses, config] => accounts(responses, config),
+        ["open-account", accounts, salt, index] => open_account(accounts, salt, index),
+        ["verify-account", opening, public_values] => verify_account(opening, public_values),
+        _ => bail!(
+            "usage:\n  por-inclusion prove <liabilities.json> <user-hash>\n  \
+             por-inclusion prove-all <liabilities.json> <out-dir>\n  \
+             por-inclusion verify <receipt.json> <public-values.json>\n  \
+             por-inclusion accounts <responses.json> <config.json>\n  \
+             por-inclusion open-account <accounts.json> <salt> <index>\n  \
+             por-inclusion verify-account <opening.json> <public-values.json>"
+        ),
+    }
+}
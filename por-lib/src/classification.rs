@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+use crate::decimal::Decimal;
+
+#[derive(Debug, Error)]
+#[error("balance overflow: {0}")]
+pub struct BalanceOverflow(pub String);
+
+/// Groups assets by category, `categories` being asset => category. Without `other`, assets out of
+/// the classification stay itemized. An unclassified asset named like a category is merged into it.
+pub fn group(
+    asset_bals: &BTreeMap<String, Decimal>,
+    categories: &BTreeMap<String, String>,
+    other: Option<&str>,
+) -> Result<BTreeMap<String, Decimal>, BalanceOverflow> {
+    let names: BTreeSet<&str> = categories.values().map(String::as_str).collect();
+    let mut grouped: BTreeMap<String, Decimal> = BTreeMap::new();
+    for (k, v) in asset_bals {
+        let key = categories
+            .get(k)
+            .map(String::as_str)
+            .or_else(|| names.get(k.as_str()).copied())
+            .or(other)
+            .unwrap_or(k);
+        let total = grouped.entry(key.to_string()).or_default();
+        *total = total.checked_add(v).ok_or_else(|| BalanceOverflow(key.to_string()))?;
+    }
+    Ok(grouped)
+}
+
+/// Summary assets by Category, as `group`, leaving out what is not positive. For display only.
+pub fn summarize(
+    asset_bals: &BTreeMap<String, Decimal>,
+    categories: &BTreeMap<String, String>,
+    other: Option<&str>,
+) -> Result<BTreeMap<String, Decimal>, BalanceOverflow> {
+    let mut grouped = group(asset_bals, categories, other)?;
+    grouped.retain(|_, v| v.is_positive());
+    Ok(grouped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bals(bals: &[(&str, &str)]) -> BTreeMap<String, Decimal> {
+        bals.iter().map(|(a, b)| (a.to_string(), b.parse().unwrap())).collect()
+    }
+
+    fn categories() -> BTreeMap<String, String> {
+        ["USDT", "USDC"]
+            .iter()
+            .map(|a| (a.to_string(), "STABLECOIN".to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_classified_assets_and_itemizes_the_others() {
+        let grouped = group(
+            &bals(&[("USDT", "1"), ("USDC", "2"), ("BTC", "3")]),
+            &categories(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(grouped, bals(&[("STABLECOIN", "3"), ("BTC", "3")]));
+
+        let grouped = group(
+            &bals(&[("USDT", "1"), ("BTC", "3"), ("ETH", "4")]),
+            &categories(),
+            Some("OTHER"),
+        )
+        .unwrap();
+        assert_eq!(grouped, bals(&[("STABLECOIN", "1"), ("OTHER", "7")]));
+    }
+
+    #[test]
+    fn merges_an_asset_into_its_namesake_category() {
+        let grouped = group(&bals(&[("USDT", "1"), ("STABLECOIN", "2")]), &categories(), None).unwrap();
+        assert_eq!(grouped, bals(&[("STABLECOIN", "3")]));
+
+        // not into `other`
+        let grouped = group(&bals(&[("STABLECOIN", "2")]), &categories(), Some("OTHER")).unwrap();
+        assert_eq!(grouped, bals(&[("STABLECOIN", "2")]));
+    }
+
+    #[test]
+    fn summarizes_positive_groups_only() {
+        let summary = summarize(
+            &bals(&[("USDT", "1"), ("USDC", "-1"), ("BTC", "-2"), ("ETH", "0")]),
+            &categories(),
+            None,
+        )
+        .unwrap();
+        assert!(summary.is_empty());
+
+        let summary = summarize(
+            &bals(&[("USDT", "2"), ("USDC", "-1"), ("BTC", "-2")]),
+            &categories(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(summary, bals(&[("STABLECOIN", "1")]));
+    }
+
+    #[test]
+    fn fails_on_overflow() {
+        let max = Decimal::from_scaled(i128::MAX, 0);
+        let asset_bals = BTreeMap::from([("USDT".to_string(), max), ("USDC".to_string(), max)]);
+        assert!(group(&asset_bals, &categories(), None).is_err());
+    }
+}
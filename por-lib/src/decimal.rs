@@ -0,0 +1,375 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// The finest precision kept, enough for 18-decimal tokens.
+pub const MAX_SCALE: u32 = 18;
+
+/// An exact decimal number: `mantissa * 10^-scale`.
+///
+/// Each value keeps the precision it was parsed with; arithmetic is carried out at the larger
+/// of the two scales, so sums of balances are exact and independent of the summation order.
+/// Serialized as a plain decimal string, e.g. `"1234.5678"`.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid decimal literal")
+    }
+}
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal { mantissa: 0, scale: 0 };
+
+    /// `mantissa * 10^-scale`, normalized.
+    pub fn from_scaled(mantissa: i128, scale: u32) -> Self {
+        assert!(scale <= MAX_SCALE, "decimal scale out of range");
+        Self { mantissa, scale }.normalize()
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.mantissa > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa < 0
+    }
+
+    /// The same value expressed with `scale` fractional digits, if it fits.
+    pub fn rescale(&self, scale: u32) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let mantissa = if scale >= self.scale {
+            self.mantissa.checked_mul(10i128.checked_pow(scale - self.scale)?)?
+        } else {
+            let factor = 10i128.pow(self.scale - scale);
+            if self.mantissa % factor != 0 {
+                return None;
+            }
+            self.mantissa / factor
+        };
+        Some(Self { mantissa, scale })
+    }
+
+    /// The mantissa of the value with `scale` fractional digits, if it fits.
+    pub fn to_scaled(self, scale: u32) -> Option<i128> {
+        self.rescale(scale).map(|d| d.mantissa)
+    }
+
+    /// Drops trailing fractional zeros, so equal values have equal representations.
+    pub fn normalize(&self) -> Self {
+        let mut d = *self;
+        while d.scale > 0 && d.mantissa % 10 == 0 {
+            d.mantissa /= 10;
+            d.scale -= 1;
+        }
+        if d.mantissa == 0 {
+            d.scale = 0;
+        }
+        d
+    }
+
+    /// Digits beyond `MAX_SCALE` are accepted only if they are zeros.
+    fn trimmed(mut mantissa: i128, mut scale: u32) -> Result<Self, ParseDecimalError> {
+        // A non-zero i128 ends in at most 38 zeros
+        if scale.saturating_sub(MAX_SCALE) > 38 {
+            return Err(ParseDecimalError);
+        }
+        while scale > MAX_SCALE {
+            if mantissa % 10 != 0 {
+                return Err(ParseDecimalError);
+            }
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Ok(Self { mantissa, scale })
+    }
+
+    /// The integer part and the fractional part at `MAX_SCALE`, both with the sign of the value.
+    /// Neither can overflow, unlike rescaling.
+    fn split(&self) -> (i128, i128) {
+        let factor = 10i128.pow(self.scale);
+        (
+            self.mantissa / factor,
+            (self.mantissa % factor) * 10i128.pow(MAX_SCALE - self.scale),
+        )
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let a = self.rescale(scale)?;
+        let b = other.rescale(scale)?;
+        Some(Self {
+            mantissa: a.mantissa.checked_add(b.mantissa)?,
+            scale,
+        })
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let a = self.rescale(scale)?;
+        let b = other.rescale(scale)?;
+        Some(Self {
+            mantissa: a.mantissa.checked_sub(b.mantissa)?,
+            scale,
+        })
+    }
+
+    /// The sum of `values`, `None` on overflow.
+    pub fn checked_sum<'a>(values: impl IntoIterator<Item = &'a Decimal>) -> Option<Self> {
+        values.into_iter().try_fold(Decimal::ZERO, |acc, d| acc.checked_add(d))
+    }
+
+    /// `|self|`, `None` on overflow.
+    pub fn checked_abs(&self) -> Option<Self> {
+        Some(Self {
+            mantissa: self.mantissa.checked_abs()?,
+            scale: self.scale,
+        })
+    }
+
+    /// `self * other` truncated to `scale` fractional digits. `None` on overflow.
+    pub fn checked_mul(&self, other: &Self, scale: u32) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let product = self.mantissa.checked_mul(other.mantissa)?;
+        let product_scale = self.scale + other.scale;
+        let mantissa = if product_scale >= scale {
+            product / 10i128.checked_pow(product_scale - scale)?
+        } else {
+            product.checked_mul(10i128.pow(scale - product_scale))?
+        };
+        Some(Self { mantissa, scale }.normalize())
+    }
+
+    /// `self / other` truncated to `scale` fractional digits. `None` if `other` is zero or on overflow.
+    pub fn checked_div(&self, other: &Self, scale: u32) -> Option<Self> {
+        let common = self.scale.max(other.scale);
+        let a = self.rescale(common)?.mantissa;
+        let b = other.rescale(common)?.mantissa;
+        if b == 0 || scale > MAX_SCALE {
+            return None;
+        }
+        let mantissa = a.checked_mul(10i128.pow(scale))?.checked_div(b)?;
+        Some(Self { mantissa, scale }.normalize())
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    /// Accepts `[+-]digits[.digits][(e|E)[+-]digits]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (num, exp) = match s.find(['e', 'E']) {
+            Some(pos) => (&s[..pos], s[pos + 1..].parse::<i32>().map_err(|_| ParseDecimalError)?),
+            None => (s, 0),
+        };
+        let (negative, num) = match num.as_bytes().first() {
+            Some(b'-') => (true, &num[1..]),
+            Some(b'+') => (false, &num[1..]),
+            _ => (false, num),
+        };
+        let (int_part, frac_part) = num.split_once('.').unwrap_or((num, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(ParseDecimalError);
+        }
+        if !int_part.bytes().chain(frac_part.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(ParseDecimalError);
+        }
+
+        let mut mantissa: i128 = 0;
+        for b in int_part.bytes().chain(frac_part.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add((b - b'0') as i128))
+                .ok_or(ParseDecimalError)?;
+        }
+        if mantissa == 0 {
+            return Ok(Decimal::ZERO);
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        let scale = frac_part.len() as i64 - exp as i64;
+        let d = if scale < 0 {
+            let zeros = u32::try_from(-scale).map_err(|_| ParseDecimalError)?;
+            let factor = 10i128.checked_pow(zeros).ok_or(ParseDecimalError)?;
+            Decimal {
+                mantissa: mantissa.checked_mul(factor).ok_or(ParseDecimalError)?,
+                scale: 0,
+            }
+        } else {
+            Decimal::trimmed(mantissa, u32::try_from(scale).map_err(|_| ParseDecimalError)?)?
+        };
+        Ok(d)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = self.normalize();
+        let sign = if d.mantissa < 0 { "-" } else { "" };
+        let digits = d.mantissa.unsigned_abs().to_string();
+        let scale = d.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, int_part, frac_part)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    // Truncating to the integer part keeps the order, the fractional parts break ties.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.split().cmp(&other.split())
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_plain_and_signed() {
+        assert_eq!(d("1234.5678"), Decimal::from_scaled(12345678, 4));
+        assert_eq!(d("-0.5"), Decimal::from_scaled(-5, 1));
+        assert_eq!(d("+7"), Decimal::from_scaled(7, 0));
+        assert_eq!(d(".5"), d("0.5"));
+        assert_eq!(d("5."), d("5"));
+        for s in ["", ".", "-", "1.2.3", "1,5", "abc", "1e", "0x10", " 1"] {
+            assert!(s.parse::<Decimal>().is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn parses_exponents() {
+        assert_eq!(d("1.5e3"), d("1500"));
+        assert_eq!(d("1.5E-3"), d("0.0015"));
+        assert_eq!(d("-2e+2"), d("-200"));
+        assert_eq!(d("1e-18"), Decimal::from_scaled(1, MAX_SCALE));
+    }
+
+    #[test]
+    fn trims_digits_beyond_max_scale() {
+        assert_eq!(d("0.1000000000000000000000"), d("0.1"));
+        assert_eq!(d("0e-30"), Decimal::ZERO);
+        assert!("0.0000000000000000001".parse::<Decimal>().is_err());
+        assert!("1e-19".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn parses_extreme_exponents_without_looping() {
+        assert_eq!(d("0e-2147483647"), Decimal::ZERO);
+        assert_eq!(d("-0.0e2147483647"), Decimal::ZERO);
+        assert!("1e-2147483647".parse::<Decimal>().is_err());
+        assert!("1e2147483647".parse::<Decimal>().is_err());
+        // 10^38 carries the most trailing zeros an i128 can
+        assert_eq!(d("100000000000000000000000000000000000000e-56"), d("1e-18"));
+        assert!("100000000000000000000000000000000000000e-57"
+            .parse::<Decimal>()
+            .is_err());
+    }
+
+    #[test]
+    fn displays_normalized_and_round_trips() {
+        for (s, shown) in [
+            ("1.500", "1.5"),
+            ("-0.010", "-0.01"),
+            ("0.000", "0"),
+            ("100", "100"),
+            ("0.000000000000000001", "0.000000000000000001"),
+            ("2.5e2", "250"),
+        ] {
+            assert_eq!(d(s).to_string(), shown);
+            assert_eq!(d(shown), d(s));
+        }
+        let json = serde_json::to_string(&d("12.34")).unwrap();
+        assert_eq!(json, "\"12.34\"");
+        assert_eq!(serde_json::from_str::<Decimal>(&json).unwrap(), d("12.34"));
+    }
+
+    #[test]
+    fn orders_across_scales() {
+        assert!(d("0.1") < d("0.11"));
+        assert!(d("-1.5") < d("-1.25"));
+        assert!(d("-0.5") < d("0.25"));
+        assert_eq!(d("1.50"), d("1.5"));
+        // rescaling either side would overflow
+        assert!(d("1e21") > d("0.000000000000000001"));
+        assert!(d("-1e21") < d("-0.000000000000000001"));
+        assert!(Decimal::from_scaled(i128::MAX, 0) > Decimal::from_scaled(i128::MAX, MAX_SCALE));
+    }
+
+    #[test]
+    fn checked_ops_fail_on_overflow() {
+        let max = Decimal::from_scaled(i128::MAX, 0);
+        let min = Decimal::from_scaled(i128::MIN, 0);
+        assert_eq!(max.checked_add(&d("1")), None);
+        assert_eq!(min.checked_sub(&d("1")), None);
+        assert_eq!(d("1e21").checked_add(&d("0.000000000000000001")), None);
+        assert_eq!(min.checked_abs(), None);
+        assert_eq!(max.checked_mul(&d("2"), 0), None);
+        assert_eq!(min.checked_div(&d("-1"), 0), None);
+        assert_eq!(d("1").checked_div(&Decimal::ZERO, 2), None);
+        assert_eq!(Decimal::checked_sum(&[max, d("1")]), None);
+        assert!("1e39".parse::<Decimal>().is_err());
+        assert!("999999999999999999999999999999999999999".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn checked_ops() {
+        assert_eq!(d("1.25").checked_add(&d("-0.5")), Some(d("0.75")));
+        assert_eq!(
+            d("1").checked_sub(&d("1.000000000000000001")),
+            Some(d("-0.000000000000000001"))
+        );
+        assert_eq!(d("1.5").checked_mul(&d("2.25"), 2), Some(d("3.37")));
+        assert_eq!(d("1").checked_div(&d("3"), 4), Some(d("0.3333")));
+        assert_eq!(d("-2").checked_abs(), Some(d("2")));
+        assert_eq!(Decimal::checked_sum(&[d("0.1"), d("0.2")]), Some(d("0.3")));
+    }
+}
@@ -0,0 +1,418 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use thiserror::Error;
+
+use crate::decimal::{Decimal, MAX_SCALE};
+use crate::merkle::{build_levels, labeled_root, proof_path, root_from_path, SumNode};
+
+/// One customer of the liability dataset.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LiabilityEntry {
+    /// Hex of the 32-byte hashed user id.
+    pub user_hash: String,
+    pub balances: BTreeMap<String, Decimal>,
+}
+
+#[derive(Debug, Error)]
+pub enum LiabilityError {
+    #[error("negative liability of {0}: {1}")]
+    NegativeLiability(String, String),
+    #[error("duplicate user: {0}")]
+    DuplicateUser(String),
+    #[error("invalid user hash: {0}")]
+    InvalidUserHash(String),
+    #[error("balance overflow")]
+    BalanceOverflow,
+    #[error("invalid inclusion proof: {0}")]
+    InvalidProof(String),
+    #[error("root mismatch: computed {computed}, committed {committed}")]
+    RootMismatch { computed: String, committed: String },
+}
+
+fn decode_user_hash(user_hash: &str) -> Result<[u8; 32], LiabilityError> {
+    hex::decode(user_hash)
+        .ok()
+        .and_then(|h| h.try_into().ok())
+        .ok_or_else(|| LiabilityError::InvalidUserHash(user_hash.to_string()))
+}
+
+fn to_sums(balances: &[Decimal]) -> Result<Vec<i128>, LiabilityError> {
+    balances
+        .iter()
+        .map(|b| b.to_scaled(MAX_SCALE))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(LiabilityError::BalanceOverflow)
+}
+
+fn to_balances(sums: &[i128]) -> Vec<Decimal> {
+    sums.iter().map(|s| Decimal::from_scaled(*s, MAX_SCALE)).collect()
+}
+
+/// The Merkle sum tree of a liability dataset.
+///
+/// Leaves are ordered by user hash; each carries the user's balance of every asset of the
+/// dataset, assets sorted by name. User hashes are lowercase and asset names uppercase.
+///
+/// An inclusion proof discloses the balances of the sibling leaf, another user's, and the sums
+/// of the subtrees above it. Ordering by hash rather than by dataset position keeps the sibling
+/// from being linked to a position in the dataset, e.g. to the account next to the user's.
+/// The committed root is `labeled_root` of the tree root and the assets, so a receipt
+/// cannot relabel its balances.
+pub struct LiabilityTree {
+    assets: Vec<String>,
+    users: HashMap<String, usize>,
+    balances: Vec<Vec<Decimal>>,
+    levels: Vec<Vec<SumNode>>,
+}
+
+impl LiabilityTree {
+    /// Builds the tree. Leaf balances must not be negative and users must be unique.
+    pub fn build(entries: &[LiabilityEntry]) -> Result<Self, LiabilityError> {
+        // Asset names are case-insensitive, as on the asset side
+        let mut normalized = vec![];
+        for entry in entries {
+            let mut balances: BTreeMap<String, Decimal> = BTreeMap::new();
+            for (asset, bal) in &entry.balances {
+                if bal.is_negative() {
+                    return Err(LiabilityError::NegativeLiability(
+                        entry.user_hash.clone(),
+                        asset.clone(),
+                    ));
+                }
+                let total = balances.entry(asset.to_ascii_uppercase()).or_default();
+                *total = total.checked_add(bal).ok_or(LiabilityError::BalanceOverflow)?;
+            }
+            normalized.push((entry.user_hash.to_ascii_lowercase(), balances));
+        }
+        normalized.sort_by(|a, b| a.0.cmp(&b.0));
+        let assets: Vec<String> = normalized
+            .iter()
+            .flat_map(|(_, bals)| bals.keys().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut users = HashMap::new();
+        let mut all_balances = vec![];
+        let mut leaves = vec![];
+        for (index, (user_hash, balances)) in normalized.into_iter().enumerate() {
+            let hash = decode_user_hash(&user_hash)?;
+            let balances: Vec<Decimal> = assets
+                .iter()
+                .map(|asset| balances.get(asset).copied().unwrap_or_default())
+                .collect();
+            leaves.push(SumNode::leaf(&hash, &to_sums(&balances)?));
+            all_balances.push(balances);
+            if users.insert(user_hash.clone(), index).is_some() {
+                return Err(LiabilityError::DuplicateUser(user_hash));
+            }
+        }
+
+        let levels = build_levels(leaves, assets.len()).ok_or(LiabilityError::BalanceOverflow)?;
+        Ok(Self {
+            assets,
+            users,
+            balances: all_balances,
+            levels,
+        })
+    }
+
+    pub fn root(&self) -> &SumNode {
+        &self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Hex of the root hash labeled with the assets, as committed in the public values.
+    pub fn root_hex(&self) -> String {
+        hex::encode(labeled_root(self.root(), &self.assets))
+    }
+
+    pub fn user_count(&self) -> u64 {
+        self.users.len() as u64
+    }
+
+    /// Total liability per asset.
+    pub fn totals(&self) -> BTreeMap<String, Decimal> {
+        self.assets
+            .iter()
+            .cloned()
+            .zip(to_balances(&self.root().sums))
+            .collect()
+    }
+
+    /// The inclusion proof of `user_hash`, `None` if the user is not in the dataset.
+    pub fn inclusion_proof(&self, user_hash: &str) -> Option<InclusionProof> {
+        let user_hash = user_hash.to_ascii_lowercase();
+        let index = *self.users.get(&user_hash)?;
+        let path = proof_path(&self.levels, index)
+            .into_iter()
+            .map(|(node, left)| PathStep {
+                hash: hex::encode(node.hash),
+                sums: to_balances(&node.sums),
+                left,
+            })
+            .collect();
+
+        Some(InclusionProof {
+            user_hash,
+            assets: self.assets.clone(),
+            balances: self.balances[index].clone(),
+            path,
+            root: self.root_hex(),
+        })
+    }
+}
+
+/// A sibling on the path from a leaf to the root.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PathStep {
+    pub hash: String,
+    pub sums: Vec<Decimal>,
+    /// Whether the sibling is the left child.
+    pub left: bool,
+}
+
+/// The receipt handed to a user: their balances and the path to the committed liability root.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionProof {
+    pub user_hash: String,
+    pub assets: Vec<String>,
+    pub balances: Vec<Decimal>, // the user's balance per asset, in `assets` order
+    pub path: Vec<PathStep>,
+    pub root: String,
+}
+
+impl InclusionProof {
+    /// Recomputes the root from the user's leaf and checks it against `committed_root`.
+    /// Returns the total liability per asset the root commits to.
+    ///
+    /// Every balance on the path must be non-negative, otherwise a sibling could hide
+    /// liabilities by offsetting them.
+    pub fn verify(&self, committed_root: &str) -> Result<BTreeMap<String, Decimal>, LiabilityError> {
+        let width = self.assets.len();
+        if self.balances.len() != width || self.path.iter().any(|step| step.sums.len() != width) {
+            return Err(LiabilityError::InvalidProof("sums do not match the assets".to_string()));
+        }
+        if self
+            .balances
+            .iter()
+            .chain(self.path.iter().flat_map(|step| &step.sums))
+            .any(|b| b.is_negative())
+        {
+            return Err(LiabilityError::InvalidProof("negative balance".to_string()));
+        }
+
+        let leaf = SumNode::leaf(&decode_user_hash(&self.user_hash)?, &to_sums(&self.balances)?);
+        let path = self
+            .path
+            .iter()
+            .map(|step| {
+                let hash = decode_user_hash(&step.hash)
+                    .map_err(|_| LiabilityError::InvalidProof(format!("invalid hash: {}", step.hash)))?;
+                Ok((
+                    SumNode {
+                        hash,
+                        sums: to_sums(&step.sums)?,
+                    },
+                    step.left,
+                ))
+            })
+            .collect::<Result<Vec<_>, LiabilityError>>()?;
+        let root = root_from_path(leaf, &path).ok_or(LiabilityError::BalanceOverflow)?;
+
+        let computed = hex::encode(labeled_root(&root, &self.assets));
+        if !computed.eq_ignore_ascii_case(committed_root) {
+            return Err(LiabilityError::RootMismatch {
+                computed,
+                committed: committed_root.to_string(),
+            });
+        }
+        Ok(self.assets.iter().cloned().zip(to_balances(&root.sums)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user: u8, balances: &[(&str, &str)]) -> LiabilityEntry {
+        LiabilityEntry {
+            user_hash: hex::encode([user; 32]),
+            balances: balances
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.parse().unwrap()))
+                .collect(),
+        }
+    }
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn tree() -> LiabilityTree {
+        LiabilityTree::build(&[
+            entry(1, &[("BTC", "0.5"), ("USDT", "100")]),
+            entry(2, &[("usdt", "20.25")]),
+            entry(3, &[("BTC", "1"), ("ETH", "2")]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn builds_totals_over_normalized_assets() {
+        let tree = tree();
+        assert_eq!(tree.user_count(), 3);
+        assert_eq!(
+            tree.totals(),
+            BTreeMap::from([
+                ("BTC".to_string(), d("1.5")),
+                ("ETH".to_string(), d("2")),
+                ("USDT".to_string(), d("120.25")),
+            ])
+        );
+    }
+
+    #[test]
+    fn orders_leaves_by_user_hash() {
+        let mut entries = vec![
+            entry(3, &[("BTC", "1"), ("ETH", "2")]),
+            entry(1, &[("BTC", "0.5"), ("USDT", "100")]),
+            entry(2, &[("usdt", "20.25")]),
+        ];
+        let root = LiabilityTree::build(&entries).unwrap().root_hex();
+        assert_eq!(root, tree().root_hex());
+        entries.swap(0, 2);
+        assert_eq!(LiabilityTree::build(&entries).unwrap().root_hex(), root);
+
+        // user 1 is the leftmost leaf, whatever its position in the dataset
+        let proof = tree().inclusion_proof(&hex::encode([1u8; 32])).unwrap();
+        assert!(proof.path.iter().all(|step| !step.left));
+    }
+
+    #[test]
+    fn every_proof_verifies_to_the_totals() {
+        let tree = tree();
+        for user in 1..=3u8 {
+            let proof = tree.inclusion_proof(&hex::encode([user; 32]).to_uppercase()).unwrap();
+            assert_eq!(proof.path.len(), 2);
+            assert_eq!(proof.verify(&tree.root_hex()).unwrap(), tree.totals());
+        }
+        assert!(tree.inclusion_proof(&hex::encode([4u8; 32])).is_none());
+    }
+
+    #[test]
+    fn proof_survives_json() {
+        let tree = tree();
+        let proof = tree.inclusion_proof(&hex::encode([3u8; 32])).unwrap();
+        let proof: InclusionProof = serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.balances, vec![d("1"), d("2"), d("0")]);
+        assert!(proof.verify(&tree.root_hex()).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampered_balances() {
+        let tree = tree();
+        let mut proof = tree.inclusion_proof(&hex::encode([1u8; 32])).unwrap();
+        proof.balances[0] = d("0.4");
+        assert!(matches!(
+            proof.verify(&tree.root_hex()),
+            Err(LiabilityError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_tampered_sibling_sums() {
+        let tree = tree();
+        let mut proof = tree.inclusion_proof(&hex::encode([1u8; 32])).unwrap();
+        proof.path[1].sums[0] = d("0.9");
+        assert!(matches!(
+            proof.verify(&tree.root_hex()),
+            Err(LiabilityError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_relabeled_assets() {
+        let tree = tree();
+        let mut proof = tree.inclusion_proof(&hex::encode([3u8; 32])).unwrap();
+        proof.assets.swap(0, 1);
+        assert!(matches!(
+            proof.verify(&tree.root_hex()),
+            Err(LiabilityError::RootMismatch { .. })
+        ));
+
+        let mut proof = tree.inclusion_proof(&hex::encode([3u8; 32])).unwrap();
+        proof.assets[2] = "DOGE".to_string();
+        assert!(matches!(
+            proof.verify(&tree.root_hex()),
+            Err(LiabilityError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_negative_siblings() {
+        let tree = tree();
+        let mut proof = tree.inclusion_proof(&hex::encode([1u8; 32])).unwrap();
+        proof.path[0].sums[2] = d("-20.25");
+        assert!(matches!(
+            proof.verify(&tree.root_hex()),
+            Err(LiabilityError::InvalidProof(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_proofs() {
+        let tree = tree();
+        let mut proof = tree.inclusion_proof(&hex::encode([1u8; 32])).unwrap();
+        proof.path[0].sums.pop();
+        assert!(matches!(
+            proof.verify(&tree.root_hex()),
+            Err(LiabilityError::InvalidProof(_))
+        ));
+
+        let mut proof = tree.inclusion_proof(&hex::encode([1u8; 32])).unwrap();
+        proof.path[0].hash = "zz".to_string();
+        assert!(matches!(
+            proof.verify(&tree.root_hex()),
+            Err(LiabilityError::InvalidProof(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_another_root() {
+        let tree = tree();
+        let other = LiabilityTree::build(&[entry(1, &[("BTC", "0.5"), ("USDT", "100")])]).unwrap();
+        let proof = tree.inclusion_proof(&hex::encode([1u8; 32])).unwrap();
+        assert!(matches!(
+            proof.verify(&other.root_hex()),
+            Err(LiabilityError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_datasets() {
+        assert!(matches!(
+            LiabilityTree::build(&[entry(1, &[("BTC", "-1")])]),
+            Err(LiabilityError::NegativeLiability(..))
+        ));
+        let mut upper = entry(1, &[]);
+        upper.user_hash = upper.user_hash.to_uppercase();
+        assert!(matches!(
+            LiabilityTree::build(&[entry(1, &[]), upper]),
+            Err(LiabilityError::DuplicateUser(_))
+        ));
+        let mut short = entry(1, &[]);
+        short.user_hash.pop();
+        assert!(matches!(
+            LiabilityTree::build(&[short]),
+            Err(LiabilityError::InvalidUserHash(_))
+        ));
+        let huge = "100000000000000000000";
+        assert!(matches!(
+            LiabilityTree::build(&[entry(1, &[("BTC", huge)]), entry(2, &[("BTC", huge)])]),
+            Err(LiabilityError::BalanceOverflow)
+        ));
+    }
+}
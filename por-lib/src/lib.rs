@@ -0,0 +1,13 @@
+//! Code shared by the zkVM program and the host: exact decimals, the freshness bounds of the
+//! attestations, the grouping of assets by category, the median of the venue prices, how the
+//! attested data sources are read, the liability Merkle sum tree and the per-user inclusion proofs
+//! against its committed root, and the salted account commitments that can be opened to an auditor.
+
+pub mod account;
+pub mod attestation;
+pub mod classification;
+pub mod decimal;
+pub mod liability;
+pub mod merkle;
+pub mod price;
+pub mod sources;
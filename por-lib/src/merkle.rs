@@ -0,0 +1,184 @@
+use sha2::{Digest, Sha256};
+
+/// Domain separators of the node hashes.
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+const ROOT_PREFIX: u8 = 0x02;
+
+/// A node of the Merkle sum tree: a hash and the per-asset sums of the leaves below it.
+///
+/// Sums are `Decimal` mantissas at `MAX_SCALE`, one per asset in the (sorted) asset order
+/// committed with the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumNode {
+    pub hash: [u8; 32],
+    pub sums: Vec<i128>,
+}
+
+impl SumNode {
+    /// The node padding an odd level: zero hash, zero sums.
+    pub fn empty(width: usize) -> Self {
+        Self {
+            hash: [0u8; 32],
+            sums: vec![0; width],
+        }
+    }
+
+    /// `H(0x00 || user_hash || balance_0 || ... || balance_n)`, balances as 16-byte big-endian.
+    pub fn leaf(user_hash: &[u8; 32], balances: &[i128]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update([LEAF_PREFIX]);
+        hasher.update(user_hash);
+        for b in balances {
+            hasher.update(b.to_be_bytes());
+        }
+        Self {
+            hash: hasher.finalize().into(),
+            sums: balances.to_vec(),
+        }
+    }
+
+    /// `H(0x01 || left.hash || left.sums || right.hash || right.sums)`, summing both sides.
+    /// `None` if a sum overflows.
+    pub fn parent(left: &SumNode, right: &SumNode) -> Option<Self> {
+        let mut hasher = Sha256::new();
+        hasher.update([NODE_PREFIX]);
+        for node in [left, right] {
+            hasher.update(node.hash);
+            for s in &node.sums {
+                hasher.update(s.to_be_bytes());
+            }
+        }
+        let sums = left
+            .sums
+            .iter()
+            .zip(&right.sums)
+            .map(|(l, r)| l.checked_add(*r))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            hash: hasher.finalize().into(),
+            sums,
+        })
+    }
+}
+
+/// `H(0x02 || root.hash || JSON of labels)`, binding the label of each sum column, e.g. the asset,
+/// to the root.
+pub fn labeled_root(root: &SumNode, labels: &[String]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([ROOT_PREFIX]);
+    hasher.update(root.hash);
+    hasher.update(serde_json::to_vec(labels).unwrap());
+    hasher.finalize().into()
+}
+
+/// Builds every level of the tree bottom-up, leaves first and root last.
+/// Odd levels are padded with `SumNode::empty`. `None` if a sum overflows.
+pub fn build_levels(leaves: Vec<SumNode>, width: usize) -> Option<Vec<Vec<SumNode>>> {
+    let mut levels = vec![leaves];
+    if levels[0].is_empty() {
+        levels[0].push(SumNode::empty(width));
+    }
+    while levels[levels.len() - 1].len() > 1 {
+        let level = levels.last_mut().unwrap();
+        if level.len() % 2 == 1 {
+            level.push(SumNode::empty(width));
+        }
+        let parents = level
+            .chunks(2)
+            .map(|pair| SumNode::parent(&pair[0], &pair[1]))
+            .collect::<Option<Vec<_>>>()?;
+        levels.push(parents);
+    }
+    Some(levels)
+}
+
+/// The siblings of leaf `index` from the bottom up, each with whether it is the left child.
+pub fn proof_path(levels: &[Vec<SumNode>], mut index: usize) -> Vec<(SumNode, bool)> {
+    let mut path = vec![];
+    for level in &levels[..levels.len() - 1] {
+        let sibling = index ^ 1;
+        path.push((level[sibling].clone(), sibling < index));
+        index /= 2;
+    }
+    path
+}
+
+/// Recomputes the root from `leaf` and its `proof_path`. `None` if a sum overflows.
+pub fn root_from_path(leaf: SumNode, path: &[(SumNode, bool)]) -> Option<SumNode> {
+    path.iter().try_fold(leaf, |node, (sibling, left)| {
+        if *left {
+            SumNode::parent(sibling, &node)
+        } else {
+            SumNode::parent(&node, sibling)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<SumNode> {
+        (0..n).map(|i| SumNode::leaf(&[i; 32], &[i as i128, 1])).collect()
+    }
+
+    #[test]
+    fn pads_odd_levels() {
+        let leaves = leaves(3);
+        let levels = build_levels(leaves.clone(), 2).unwrap();
+        assert_eq!(levels.iter().map(Vec::len).collect::<Vec<_>>(), vec![4, 2, 1]);
+        assert_eq!(levels[0][3], SumNode::empty(2));
+
+        let left = SumNode::parent(&leaves[0], &leaves[1]).unwrap();
+        let right = SumNode::parent(&leaves[2], &SumNode::empty(2)).unwrap();
+        let root = SumNode::parent(&left, &right).unwrap();
+        assert_eq!(levels[2][0], root);
+        assert_eq!(root.sums, vec![3, 3]);
+    }
+
+    #[test]
+    fn empty_tree_is_one_empty_leaf() {
+        let levels = build_levels(vec![], 2).unwrap();
+        assert_eq!(levels, vec![vec![SumNode::empty(2)]]);
+    }
+
+    #[test]
+    fn every_path_leads_to_the_root() {
+        for n in 1..=9 {
+            let leaves = leaves(n);
+            let levels = build_levels(leaves.clone(), 2).unwrap();
+            let root = &levels[levels.len() - 1][0];
+            for (index, leaf) in leaves.into_iter().enumerate() {
+                let path = proof_path(&levels, index);
+                assert_eq!(root_from_path(leaf, &path).as_ref(), Some(root), "{} of {}", index, n);
+            }
+        }
+    }
+
+    #[test]
+    fn parent_fails_on_overflow() {
+        let a = SumNode::leaf(&[1; 32], &[i128::MAX]);
+        let b = SumNode::leaf(&[2; 32], &[1]);
+        assert_eq!(SumNode::parent(&a, &b), None);
+        assert_eq!(build_levels(vec![a, b], 1), None);
+    }
+
+    #[test]
+    fn hashes_are_domain_separated() {
+        let leaf = SumNode::leaf(&[0; 32], &[]);
+        let node = SumNode::parent(&SumNode::empty(0), &SumNode::empty(0)).unwrap();
+        assert_ne!(leaf.hash, node.hash);
+        assert_ne!(labeled_root(&node, &[]), node.hash);
+    }
+
+    #[test]
+    fn labeled_root_binds_the_labels_in_order() {
+        let root = SumNode::leaf(&[1; 32], &[1, 2]);
+        let labels = |l: &[&str]| l.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let btc_eth = labeled_root(&root, &labels(&["BTC", "ETH"]));
+        assert_eq!(btc_eth, labeled_root(&root, &labels(&["BTC", "ETH"])));
+        assert_ne!(btc_eth, labeled_root(&root, &labels(&["ETH", "BTC"])));
+        assert_ne!(btc_eth, labeled_root(&root, &labels(&["BTCE", "TH"])));
+    }
+}
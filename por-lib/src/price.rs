@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::decimal::{Decimal, MAX_SCALE};
+
+#[derive(Debug, Error)]
+pub enum PriceError {
+    #[error("missing price: {0}")]
+    MissingPriceData(String),
+    #[error("price deviation: {0}")]
+    PriceDeviation(String),
+    #[error("balance overflow: {0}")]
+    BalanceOverflow(String),
+}
+
+/// `None` on overflow.
+fn median(prices: &mut [Decimal]) -> Option<Decimal> {
+    prices.sort();
+    let mid = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        return Some(prices[mid]);
+    }
+    prices[mid - 1]
+        .checked_add(&prices[mid])?
+        .checked_div(&Decimal::from_scaled(2, 0), MAX_SCALE)
+}
+
+/// Median of the venue prices of `symbol`. Fails if fewer than `min_venues` venues quote it,
+/// or if a venue deviates from the median by more than `max_deviation`, relative to the median.
+pub fn median_price(
+    symbol: &str,
+    prices: &BTreeMap<String, Vec<Decimal>>,
+    min_venues: usize,
+    max_deviation: &Decimal,
+) -> Result<Decimal, PriceError> {
+    let mut venue_prices = prices.get(symbol).cloned().unwrap_or_default();
+    let min_venues = min_venues.max(1);
+    if venue_prices.len() < min_venues {
+        return Err(PriceError::MissingPriceData(format!(
+            "{} quoted by {} venues, {} required",
+            symbol,
+            venue_prices.len(),
+            min_venues
+        )));
+    }
+    let overflow = || PriceError::BalanceOverflow(symbol.to_string());
+    let median = median(&mut venue_prices).ok_or_else(overflow)?;
+
+    let tolerance = max_deviation.checked_mul(&median, MAX_SCALE).ok_or_else(overflow)?;
+    for price in &venue_prices {
+        let deviation = price
+            .checked_sub(&median)
+            .and_then(|d| d.checked_abs())
+            .ok_or_else(overflow)?;
+        if deviation > tolerance {
+            return Err(PriceError::PriceDeviation(format!(
+                "{} {} off median {}",
+                symbol, price, median
+            )));
+        }
+    }
+
+    Ok(median)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn prices(quotes: &[&str]) -> BTreeMap<String, Vec<Decimal>> {
+        BTreeMap::from([("BTCUSDT".to_string(), quotes.iter().map(|p| d(p)).collect())])
+    }
+
+    #[test]
+    fn takes_the_middle_price() {
+        assert_eq!(median(&mut [d("3"), d("1"), d("2")]), Some(d("2")));
+        assert_eq!(median(&mut [d("7")]), Some(d("7")));
+    }
+
+    #[test]
+    fn averages_the_middle_prices_of_an_even_count() {
+        assert_eq!(median(&mut [d("4"), d("1"), d("3"), d("100")]), Some(d("3.5")));
+        assert_eq!(median(&mut [d("1"), d("2")]), Some(d("1.5")));
+        assert_eq!(median(&mut [d("0.1"), d("0.2")]), Some(d("0.15")));
+    }
+
+    #[test]
+    fn accepts_a_deviation_at_the_bound() {
+        // median 100, 1% of it is 1
+        let median = median_price("BTCUSDT", &prices(&["99", "100", "101"]), 3, &d("0.01")).unwrap();
+        assert_eq!(median, d("100"));
+        assert!(matches!(
+            median_price("BTCUSDT", &prices(&["98.99", "100", "101"]), 3, &d("0.01")),
+            Err(PriceError::PriceDeviation(_))
+        ));
+        assert!(matches!(
+            median_price("BTCUSDT", &prices(&["99", "100", "101.01"]), 3, &d("0.01")),
+            Err(PriceError::PriceDeviation(_))
+        ));
+    }
+
+    #[test]
+    fn checks_the_deviation_from_an_even_count_median() {
+        // median 100.5, 1% of it is 1.005
+        assert!(median_price("BTCUSDT", &prices(&["100", "101"]), 2, &d("0.01")).is_ok());
+        assert!(matches!(
+            median_price("BTCUSDT", &prices(&["99", "102"]), 2, &d("0.01")),
+            Err(PriceError::PriceDeviation(_))
+        ));
+    }
+
+    #[test]
+    fn requires_min_venues() {
+        assert!(matches!(
+            median_price("BTCUSDT", &prices(&["100", "100"]), 3, &d("0.01")),
+            Err(PriceError::MissingPriceData(_))
+        ));
+        assert!(matches!(
+            median_price("ETHUSDT", &prices(&["100"]), 0, &d("0.01")),
+            Err(PriceError::MissingPriceData(_))
+        ));
+    }
+}
@@ -0,0 +1,58 @@
+use super::{BalanceRow, DataSource, SourceError};
+
+const ASTER_SPOT_BALANCE_URL: &str = "https://sapi.asterdex.com/api/v1/account";
+const ASTER_FEATURE_BALANCE_URL: &str = "https://fapi.asterdex.com/fapi/v2/balance";
+
+pub struct AsterSpot;
+
+impl DataSource for AsterSpot {
+    fn key(&self) -> &'static str {
+        "asterSpot"
+    }
+    fn venue(&self) -> &'static str {
+        "aster"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[ASTER_SPOT_BALANCE_URL]
+    }
+    fn uid_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.updateTime"]
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.balances[*].asset", "$.balances[*].free", "$.balances[*].locked"]
+    }
+    // The response has no uid, the account is identified by updateTime and its balances
+    fn dedupe_key(&self, _url: &str, uid: &[String], rows: &[BalanceRow]) -> Result<Option<String>, SourceError> {
+        if uid.len() != 1 {
+            return Err(SourceError::InvalidJsonValueSize);
+        }
+
+        let mut balances: Vec<String> = rows
+            .iter()
+            .map(|row| format!("{}:{}", row.asset, row.amounts.join(":")))
+            .collect();
+        balances.sort();
+        let balances = balances.join(",");
+        Ok((!balances.is_empty()).then(|| format!("{}:{}", uid[0], balances)))
+    }
+}
+
+pub struct AsterUsdSFuture;
+
+impl DataSource for AsterUsdSFuture {
+    fn key(&self) -> &'static str {
+        "asterUsdSFuture"
+    }
+    fn venue(&self) -> &'static str {
+        "aster"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[ASTER_FEATURE_BALANCE_URL]
+    }
+    fn uid_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.[*].accountAlias"]
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.[*].asset", "$.[*].balance", "$.[*].crossUnPnl"]
+    }
+}
@@ -0,0 +1,368 @@
+use super::{paired_dedupe_key, BalanceRow, DataSource, SourceError};
+use crate::decimal::Decimal;
+
+const RISK_URL: &str = "https://papi.binance.com/papi/v1/um/positionRisk";
+const BALANCE_URL: &str = "https://papi.binance.com/papi/v1/balance";
+const SPOT_BALANCE_URL: &str = "https://api.binance.com/api/v3/account";
+const FEATURE_BALANCE_URL: &str = "https://fapi.binance.com/fapi/v3/balance";
+const COIN_FEATURE_BALANCE_URL: &str = "https://dapi.binance.com/dapi/v1/balance";
+const EARN_FLEXIBLE_URL: &str = "https://api.binance.com/sapi/v1/simple-earn/flexible/position";
+const EARN_LOCKED_URL: &str = "https://api.binance.com/sapi/v1/simple-earn/locked/position";
+const FUNDING_URL: &str = "https://api.binance.com/sapi/v1/asset/get-funding-asset";
+const CROSS_MARGIN_URL: &str = "https://api.binance.com/sapi/v1/margin/account";
+const TICKER_PRICE_URL: &str = "https://api.binance.com/api/v3/ticker/price";
+const ISOLATED_MARGIN_URL: &str = "https://api.binance.com/sapi/v1/margin/isolated/account";
+
+/// Net asset of a margin row `[free, locked, borrowed, interest]`, `None` on overflow.
+fn margin_net_asset(amounts: &[Decimal]) -> Option<Decimal> {
+    amounts[0]
+        .checked_add(&amounts[1])?
+        .checked_sub(&amounts[2])?
+        .checked_sub(&amounts[3])
+}
+
+pub struct BinanceSpot;
+
+impl DataSource for BinanceSpot {
+    fn key(&self) -> &'static str {
+        "binanceSpot"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL]
+    }
+    fn uid_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.uid"]
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.balances[*].asset", "$.balances[*].free", "$.balances[*].locked"]
+    }
+    fn dedupe_key(&self, _url: &str, uid: &[String], _rows: &[BalanceRow]) -> Result<Option<String>, SourceError> {
+        if uid.len() != 1 {
+            return Err(SourceError::InvalidJsonValueSize);
+        }
+        Ok(Some(uid[0].clone()))
+    }
+}
+
+pub struct BinanceUsdSFuture;
+
+impl DataSource for BinanceUsdSFuture {
+    fn key(&self) -> &'static str {
+        "binanceUsdSFuture"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[FEATURE_BALANCE_URL]
+    }
+    fn uid_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.[*].accountAlias"]
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.[*].asset", "$.[*].balance", "$.[*].crossUnPnl"]
+    }
+}
+
+pub struct BinanceCoinFuture;
+
+impl DataSource for BinanceCoinFuture {
+    fn key(&self) -> &'static str {
+        "binanceCoinFuture"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[COIN_FEATURE_BALANCE_URL]
+    }
+    fn uid_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.[*].accountAlias"]
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.[*].asset", "$.[*].balance", "$.[*].crossUnPnl"]
+    }
+}
+
+// The portfolio margin balance carries no account id, so each one is preceded by the spot account
+// or, as the client sends, by the UM positions.
+pub struct BinanceUnified;
+
+impl DataSource for BinanceUnified {
+    fn key(&self) -> &'static str {
+        "binanceUnified"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[RISK_URL, SPOT_BALANCE_URL, BALANCE_URL]
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            RISK_URL => &["$.[*].symbol", "$.[*].entryPrice"],
+            SPOT_BALANCE_URL => &["$.uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            BALANCE_URL => &[
+                "$.[*].asset",
+                "$.[*].crossMarginFree",
+                "$.[*].crossMarginLocked",
+                "$.[*].crossMarginBorrowed",
+                "$.[*].crossMarginInterest",
+                "$.[*].umWalletBalance",
+                "$.[*].umUnrealizedPNL",
+                "$.[*].cmWalletBalance",
+                "$.[*].cmUnrealizedPNL",
+            ],
+            _ => &[],
+        }
+    }
+    // Net equity, from the parts of totalWalletBalance (cross margin free + locked, UM and CM wallet):
+    // margin net asset + UM wallet + UM PNL + CM wallet + CM PNL
+    fn combine(&self, amounts: &[Decimal]) -> Option<Decimal> {
+        margin_net_asset(&amounts[..4])?.checked_add(&Decimal::checked_sum(&amounts[4..])?)
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL, RISK_URL]
+    }
+    // The account is identified by its uid, or by its UM (asset => entryPrice) info, none without UM positions
+    fn dedupe_key(&self, url: &str, uid: &[String], _rows: &[BalanceRow]) -> Result<Option<String>, SourceError> {
+        if url != RISK_URL {
+            return paired_dedupe_key(url, uid, self.id_urls());
+        }
+        if !uid.len().is_multiple_of(2) {
+            return Err(SourceError::InvalidJsonValueSize);
+        }
+
+        let size = uid.len() / 2;
+        let mut prices: Vec<String> = (0..size)
+            .map(|j| format!("{}:{}", uid[j].to_ascii_uppercase(), uid[size + j]))
+            .collect();
+        prices.sort();
+        let um_price = prices.join(",");
+        Ok((!um_price.is_empty()).then_some(um_price))
+    }
+}
+
+// The earn and funding responses carry no account id, so each one is preceded by the spot account.
+pub struct BinanceEarnFlexible;
+
+impl DataSource for BinanceEarnFlexible {
+    fn key(&self) -> &'static str {
+        "binanceEarnFlexible"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL, EARN_FLEXIBLE_URL]
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            SPOT_BALANCE_URL => &["$.uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            EARN_FLEXIBLE_URL => &["$.rows[*].asset", "$.rows[*].totalAmount"],
+            _ => &[],
+        }
+    }
+    // The spot wallet holds the positions as `LD<asset>`.
+    fn receipt_prefix(&self) -> Option<&'static str> {
+        Some("LD")
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL]
+    }
+}
+
+pub struct BinanceEarnLocked;
+
+impl DataSource for BinanceEarnLocked {
+    fn key(&self) -> &'static str {
+        "binanceEarnLocked"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL, EARN_LOCKED_URL]
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            SPOT_BALANCE_URL => &["$.uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            EARN_LOCKED_URL => &["$.rows[*].asset", "$.rows[*].amount"],
+            _ => &[],
+        }
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL]
+    }
+}
+
+pub struct BinanceFunding;
+
+impl DataSource for BinanceFunding {
+    fn key(&self) -> &'static str {
+        "binanceFunding"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL, FUNDING_URL]
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            SPOT_BALANCE_URL => &["$.uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            FUNDING_URL => &[
+                "$.[*].asset",
+                "$.[*].free",
+                "$.[*].locked",
+                "$.[*].freeze",
+                "$.[*].withdrawing",
+            ],
+            _ => &[],
+        }
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL]
+    }
+}
+
+// Margin debt is netted out: netAsset = free + locked - borrowed - interest
+pub struct BinanceCrossMargin;
+
+impl DataSource for BinanceCrossMargin {
+    fn key(&self) -> &'static str {
+        "binanceCrossMargin"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL, CROSS_MARGIN_URL]
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            SPOT_BALANCE_URL => &["$.uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            CROSS_MARGIN_URL => &[
+                "$.userAssets[*].asset",
+                "$.userAssets[*].free",
+                "$.userAssets[*].locked",
+                "$.userAssets[*].borrowed",
+                "$.userAssets[*].interest",
+            ],
+            _ => &[],
+        }
+    }
+    fn combine(&self, amounts: &[Decimal]) -> Option<Decimal> {
+        margin_net_asset(amounts)
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL]
+    }
+}
+
+// Each isolated pair lists its base and quote asset
+pub struct BinanceIsolatedMargin;
+
+impl DataSource for BinanceIsolatedMargin {
+    fn key(&self) -> &'static str {
+        "binanceIsolatedMargin"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL, ISOLATED_MARGIN_URL]
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            SPOT_BALANCE_URL => &["$.uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            ISOLATED_MARGIN_URL => &[
+                "$.assets[*].baseAsset.asset",
+                "$.assets[*].baseAsset.free",
+                "$.assets[*].baseAsset.locked",
+                "$.assets[*].baseAsset.borrowed",
+                "$.assets[*].baseAsset.interest",
+            ],
+            _ => &[],
+        }
+    }
+    fn extra_bal_paths(&self, url: &str) -> &'static [&'static [&'static str]] {
+        match url {
+            ISOLATED_MARGIN_URL => &[&[
+                "$.assets[*].quoteAsset.asset",
+                "$.assets[*].quoteAsset.free",
+                "$.assets[*].quoteAsset.locked",
+                "$.assets[*].quoteAsset.borrowed",
+                "$.assets[*].quoteAsset.interest",
+            ]],
+            _ => &[],
+        }
+    }
+    fn combine(&self, amounts: &[Decimal]) -> Option<Decimal> {
+        margin_net_asset(amounts)
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[SPOT_BALANCE_URL]
+    }
+}
+
+// Not a venue: the latest prices of all symbols, reported as symbol => price
+pub struct BinanceTickerPrice;
+
+impl DataSource for BinanceTickerPrice {
+    fn key(&self) -> &'static str {
+        "binanceTickerPrice"
+    }
+    fn venue(&self) -> &'static str {
+        "binance"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[TICKER_PRICE_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.[*].symbol", "$.[*].price"]
+    }
+    // One response, so every symbol has one price
+    fn check_requests(&self, len: usize) -> Result<(), SourceError> {
+        if len != 1 {
+            return Err(SourceError::InvalidRequestLength);
+        }
+        Ok(())
+    }
+}
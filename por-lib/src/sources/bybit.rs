@@ -0,0 +1,70 @@
+use super::{DataSource, SourceError};
+
+const BYBIT_API_KEY_URL: &str = "https://api.bybit.com/v5/user/query-api";
+const BYBIT_UNIFIED_BALANCE_URL: &str = "https://api.bybit.com/v5/account/wallet-balance?accountType=UNIFIED";
+const BYBIT_TICKERS_URL: &str = "https://api.bybit.com/v5/market/tickers?category=spot";
+
+// The wallet balance carries no account id, so each one is preceded by the API key info.
+pub struct BybitUnified;
+
+impl DataSource for BybitUnified {
+    fn key(&self) -> &'static str {
+        "bybitUnified"
+    }
+    fn venue(&self) -> &'static str {
+        "bybit"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[BYBIT_API_KEY_URL, BYBIT_UNIFIED_BALANCE_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            BYBIT_API_KEY_URL => &["$.result.userID"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            BYBIT_UNIFIED_BALANCE_URL => &[
+                "$.result.list[*].coin[*].coin",
+                "$.result.list[*].coin[*].walletBalance",
+                "$.result.list[*].coin[*].unrealisedPnl",
+            ],
+            _ => &[],
+        }
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[BYBIT_API_KEY_URL]
+    }
+}
+
+// Not a venue: the last prices of all spot symbols, reported as symbol => price
+pub struct BybitTickerPrice;
+
+impl DataSource for BybitTickerPrice {
+    fn key(&self) -> &'static str {
+        "bybitTickerPrice"
+    }
+    fn venue(&self) -> &'static str {
+        "bybit"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[BYBIT_TICKERS_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.result.list[*].symbol", "$.result.list[*].lastPrice"]
+    }
+    // One response, so every symbol has one price
+    fn check_requests(&self, len: usize) -> Result<(), SourceError> {
+        if len != 1 {
+            return Err(SourceError::InvalidRequestLength);
+        }
+        Ok(())
+    }
+}
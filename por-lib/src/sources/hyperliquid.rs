@@ -0,0 +1,68 @@
+use serde_json::Value;
+
+use super::{parse_address, DataSource, SourceError};
+
+const HYPERLIQUID_INFO_URL: &str = "https://api.hyperliquid.xyz/info";
+
+// Both info requests are POSTs of `{"type": .., "user": "0x.."}`, the account is the wallet of the body.
+fn body_user(body: &str, info_type: &str) -> Result<Vec<String>, SourceError> {
+    let body: Value = serde_json::from_str(body).map_err(|e| SourceError::InvalidRequestBody(e.to_string()))?;
+    if body["type"] != info_type {
+        return Err(SourceError::InvalidRequestBody(body["type"].to_string()));
+    }
+    let user = body["user"]
+        .as_str()
+        .and_then(parse_address)
+        .ok_or_else(|| SourceError::InvalidRequestBody(body["user"].to_string()))?;
+    Ok(vec![user])
+}
+
+pub struct HyperliquidPerp;
+
+impl DataSource for HyperliquidPerp {
+    fn key(&self) -> &'static str {
+        "hyperliquidPerp"
+    }
+    fn venue(&self) -> &'static str {
+        "hyperliquid"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[HYPERLIQUID_INFO_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn body_uid(&self, _url: &str, body: &str) -> Result<Vec<String>, SourceError> {
+        body_user(body, "clearinghouseState")
+    }
+    // The account value is margined in USDC.
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.marginSummary.accountValue"]
+    }
+    fn fixed_asset(&self, _url: &str) -> Option<&'static str> {
+        Some("USDC")
+    }
+}
+
+pub struct HyperliquidSpot;
+
+impl DataSource for HyperliquidSpot {
+    fn key(&self) -> &'static str {
+        "hyperliquidSpot"
+    }
+    fn venue(&self) -> &'static str {
+        "hyperliquid"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[HYPERLIQUID_INFO_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn body_uid(&self, _url: &str, body: &str) -> Result<Vec<String>, SourceError> {
+        body_user(body, "spotClearinghouseState")
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.balances[*].coin", "$.balances[*].total"]
+    }
+}
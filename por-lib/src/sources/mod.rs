@@ -0,0 +1,589 @@
+//! The data sources of the asset balances and how their attested responses are read, shared by the
+//! zkVM program, which verifies the attestations first, and the host, which rebuilds the accounts
+//! of a run from the same responses.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use thiserror::Error;
+
+use crate::account::AccountEntry;
+use crate::decimal::Decimal;
+
+mod aster;
+mod binance;
+mod bybit;
+mod hyperliquid;
+mod okx;
+pub mod onchain;
+
+#[derive(Debug, Error)]
+pub enum SourceError {
+    #[error("invalid request length")]
+    InvalidRequestLength,
+    #[error("invalid request order")]
+    InvalidRequestOrder,
+    #[error("invalid request url")]
+    InvalidRequestUrl,
+    #[error("invalid request body: {0}")]
+    InvalidRequestBody(String),
+    #[error("invalid rpc request: {0}")]
+    InvalidRpcRequest(String),
+    #[error("block number mismatch: {0}")]
+    BlockNumberMismatch(String),
+    #[error("timestamp not found")]
+    CannotFoundTimestamp,
+    #[error("invalid timestamp")]
+    ParseTimestampFailed,
+    #[error("cannot get json values: {0}")]
+    GetJsonValueFail(String),
+    #[error("invalid json value size")]
+    InvalidJsonValueSize,
+    #[error("invalid balance: {0}")]
+    ParseBalanceFailed(String),
+    #[error("balance overflow: {0}")]
+    BalanceOverflow(String),
+    #[error("no account for {0}")]
+    MissingAccountId(String),
+    #[error("duplicate account: {0}")]
+    DuplicateAccount(String),
+}
+
+/// The response of an attested request, read by JSONPath.
+pub trait Response {
+    /// The values at `paths`, path by path, as JSON text.
+    fn json_values(&self, paths: &[&str]) -> Result<Vec<String>, String>;
+}
+
+/// The values at `path` of `value`, for the JSONPath subset of the sources: `$`, `.name`, `[*]` and `.[*]`.
+fn select<'a>(value: &'a Value, path: &str) -> Result<Vec<&'a Value>, String> {
+    let mut rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| format!("unsupported path {}", path))?;
+    let mut values = vec![value];
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix(".[*]").or_else(|| rest.strip_prefix("[*]")) {
+            values = values
+                .into_iter()
+                .flat_map(|v| v.as_array().into_iter().flatten())
+                .collect();
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('.') {
+            let (name, r) = r.split_at(r.find(['.', '[']).unwrap_or(r.len()));
+            if name.is_empty() {
+                return Err(format!("unsupported path {}", path));
+            }
+            values = values.into_iter().filter_map(|v| v.get(name)).collect();
+            rest = r;
+        } else {
+            return Err(format!("unsupported path {}", path));
+        }
+    }
+    Ok(values)
+}
+
+/// A response the host kept, read as the attestation's.
+impl Response for Value {
+    fn json_values(&self, paths: &[&str]) -> Result<Vec<String>, String> {
+        let mut values = vec![];
+        for path in paths {
+            values.extend(select(self, path)?.into_iter().map(Value::to_string));
+        }
+        Ok(values)
+    }
+}
+
+/// One attested request and its response.
+pub struct AttestedRequest<'a, R> {
+    pub url: &'a str,
+    pub body: &'a str,
+    pub response: R,
+}
+
+/// One row extracted by `DataSource::bal_paths`: the asset and its raw amount fields.
+pub struct BalanceRow {
+    pub asset: String,
+    pub amounts: Vec<String>,
+    pub paths: &'static [&'static str], // JSONPaths of `amounts`
+}
+
+/// A venue API whose attested responses contribute to the asset balances.
+///
+/// Implementors only describe *what* to extract; `extract` does the
+/// timestamp parse -> URL check -> JSONPath extract -> dedupe loop.
+pub trait DataSource {
+    /// Key of the attestation map, e.g. `binanceSpot`.
+    fn key(&self) -> &'static str;
+
+    /// Source the balances are reported under, e.g. `binance`.
+    fn venue(&self) -> &'static str;
+
+    /// Base URLs the requests must start with.
+    fn urls(&self) -> &'static [&'static str];
+
+    /// Whether the requests carry a `timestamp=` parameter.
+    /// Otherwise the time of the attestation is used for every request.
+    fn timestamp_in_url(&self) -> bool {
+        true
+    }
+
+    /// JSONPaths identifying the account of a response of `url`.
+    fn uid_paths(&self, _url: &str) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Values identifying the account in the body of a request of `url`, appended to `uid`.
+    /// Also the place to check the body.
+    fn body_uid(&self, _url: &str, _body: &str) -> Result<Vec<String>, SourceError> {
+        Ok(vec![])
+    }
+
+    /// JSONPaths of the balances of a response of `url`.
+    /// The first path is the asset, the others are the amounts passed to `combine`.
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Further path sets of a response of `url` listing balances in several places,
+    /// each extracted like `bal_paths`.
+    fn extra_bal_paths(&self, _url: &str) -> &'static [&'static [&'static str]] {
+        &[]
+    }
+
+    /// The asset of a response of `url` that carries none; then every path of `bal_paths` is an amount.
+    fn fixed_asset(&self, _url: &str) -> Option<&'static str> {
+        None
+    }
+
+    /// The asset a row of `asset` is reported under, `None` to leave the row out.
+    fn normalize_asset(&self, asset: String) -> Option<String> {
+        Some(asset)
+    }
+
+    /// Combines the amounts of one row into the asset balance, `None` on overflow.
+    fn combine(&self, amounts: &[Decimal]) -> Option<Decimal> {
+        Decimal::checked_sum(amounts)
+    }
+
+    /// Prefix of the receipt tokens other sources of the venue list the positions of this source as,
+    /// e.g. `LD` for `LDBTC`. The receipts of the positions verified are left out of the venue balances,
+    /// in the accounts of the same id only.
+    fn receipt_prefix(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// For sources whose balance responses carry no account id, the URLs of the requests identifying
+    /// the account, each balance request being preceded by one.
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Checks on the number of requests, e.g. pairing.
+    fn check_requests(&self, len: usize) -> Result<(), SourceError> {
+        match self.id_urls() {
+            [] => Ok(()),
+            _ => check_paired_requests(len),
+        }
+    }
+
+    /// Checks the position of a request of `url`.
+    fn check_order(&self, index: usize, url: &str) -> Result<(), SourceError> {
+        match self.id_urls() {
+            [] => Ok(()),
+            id_urls => check_paired_order(index, url, id_urls),
+        }
+    }
+
+    /// The key used to detect the same account attested twice, also the id of the account.
+    /// `uid` holds the values of `uid_paths`, path by path, then those of `body_uid`. `None` means the response carries no account.
+    fn dedupe_key(&self, url: &str, uid: &[String], _rows: &[BalanceRow]) -> Result<Option<String>, SourceError> {
+        match self.id_urls() {
+            [] => Ok(uid.first().cloned()),
+            id_urls => paired_dedupe_key(url, uid, id_urls),
+        }
+    }
+}
+
+// Helpers of the sources whose balance responses carry no account id: each balance request
+// is preceded by a request of one of `id_urls` identifying the account.
+// strict order: id1 bal1 id2 bal2 ...
+fn check_paired_requests(len: usize) -> Result<(), SourceError> {
+    if !len.is_multiple_of(2) {
+        return Err(SourceError::InvalidRequestLength);
+    }
+    Ok(())
+}
+
+fn check_paired_order(index: usize, url: &str, id_urls: &[&str]) -> Result<(), SourceError> {
+    if id_urls.contains(&url) != index.is_multiple_of(2) {
+        return Err(SourceError::InvalidRequestOrder);
+    }
+    Ok(())
+}
+
+fn paired_dedupe_key(url: &str, uid: &[String], id_urls: &[&str]) -> Result<Option<String>, SourceError> {
+    if !id_urls.contains(&url) {
+        return Ok(None);
+    }
+    if uid.len() != 1 {
+        return Err(SourceError::InvalidJsonValueSize);
+    }
+    Ok(Some(uid[0].clone()))
+}
+
+/// Lowercase `0x`-prefixed 20-byte hex address, if `address` is one.
+fn parse_address(address: &str) -> Option<String> {
+    let hex = address.strip_prefix("0x")?;
+    (hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit())).then(|| format!("0x{}", hex.to_ascii_lowercase()))
+}
+
+/// All supported data sources, in the order their attestation meta is committed.
+pub static REGISTRY: &[&(dyn DataSource + Sync)] = &[
+    &binance::BinanceSpot,
+    &binance::BinanceUsdSFuture,
+    &binance::BinanceCoinFuture,
+    &binance::BinanceUnified,
+    &binance::BinanceEarnFlexible,
+    &binance::BinanceEarnLocked,
+    &binance::BinanceFunding,
+    &binance::BinanceCrossMargin,
+    &binance::BinanceIsolatedMargin,
+    &aster::AsterSpot,
+    &aster::AsterUsdSFuture,
+    &okx::OkxTrading,
+    &okx::OkxFunding,
+    &bybit::BybitUnified,
+    &hyperliquid::HyperliquidPerp,
+    &hyperliquid::HyperliquidSpot,
+];
+
+/// Ticker prices of the USD valuation, verified like the balances and reported as symbol => price,
+/// symbols as `<asset><quote>`.
+pub static PRICE_SOURCES: &[&(dyn DataSource + Sync)] = &[
+    &binance::BinanceTickerPrice,
+    &okx::OkxTickerPrice,
+    &bybit::BybitTickerPrice,
+];
+
+/// All venues, each reported under its own `asset_balance` source.
+pub const VENUES: &[&str] = &["binance", "aster", "okx", "bybit", "hyperliquid"];
+
+/// How balance fields that are not numbers are handled.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceParseMode {
+    /// Any non-numeric balance aborts the proof.
+    #[default]
+    Strict,
+    /// Empty (`""` or `null`) balances count as zero, other non-numeric balances still abort the proof.
+    Lenient,
+}
+
+fn parse_timestamp(url: &str) -> Result<u128, SourceError> {
+    url.split("timestamp=")
+        .nth(1)
+        .and_then(|s| s.split('&').next())
+        .filter(|s| !s.is_empty())
+        .ok_or(SourceError::CannotFoundTimestamp)?
+        .parse::<u128>()
+        .map_err(|_| SourceError::ParseTimestampFailed)
+}
+
+fn parse_balance(value: &str, asset: &str, path: &str, mode: BalanceParseMode) -> Result<Decimal, SourceError> {
+    if mode == BalanceParseMode::Lenient && (value.is_empty() || value == "null") {
+        return Ok(Decimal::ZERO);
+    }
+    value.parse().map_err(|_| {
+        let field = path.rsplit('.').next().unwrap_or(path);
+        SourceError::ParseBalanceFailed(format!("{} {}: {:?}", asset, field, value))
+    })
+}
+
+/// Adds `bal` to the balance of `asset`, failing on overflow.
+pub fn add_balance(bals: &mut BTreeMap<String, Decimal>, asset: &str, bal: &Decimal) -> Result<(), SourceError> {
+    let total = bals.entry(asset.to_string()).or_default();
+    *total = total
+        .checked_add(bal)
+        .ok_or_else(|| SourceError::BalanceOverflow(asset.to_string()))?;
+    Ok(())
+}
+
+/// Reads the balances of the attested `requests` of `source` into `asset_bals` and, if given, into
+/// one `accounts` entry per account, in request order. Returns the earliest and the latest request time,
+/// `attested_at` for the sources of no `timestamp=` parameter.
+pub fn extract<R: Response>(
+    source: &dyn DataSource,
+    requests: &[AttestedRequest<R>],
+    attested_at: u128,
+    mode: BalanceParseMode,
+    asset_bals: &mut BTreeMap<String, Decimal>,
+    mut accounts: Option<&mut Vec<AccountEntry>>, // None for sources of no accounts, e.g. prices
+) -> Result<(u128, u128), SourceError> {
+    if requests.is_empty() {
+        return Err(SourceError::InvalidRequestLength);
+    }
+    source.check_requests(requests.len())?;
+
+    let mut timestamp = u128::MAX;
+    let mut timestamp_max = 0;
+    let mut uids = vec![];
+    let mut account = None; // index in `accounts` of the account the rows belong to
+    for (i, request) in requests.iter().enumerate() {
+        let ts = if source.timestamp_in_url() {
+            parse_timestamp(request.url)?
+        } else {
+            attested_at
+        };
+        timestamp = timestamp.min(ts);
+        timestamp_max = timestamp_max.max(ts);
+
+        // check url
+        let url = *source
+            .urls()
+            .iter()
+            .find(|u| request.url.starts_with(*u))
+            .ok_or(SourceError::InvalidRequestUrl)?;
+        source.check_order(i, url)?;
+
+        let uid_paths = source.uid_paths(url);
+        let mut uid: Vec<String> = if uid_paths.is_empty() {
+            vec![]
+        } else {
+            request
+                .response
+                .json_values(uid_paths)
+                .map_err(SourceError::GetJsonValueFail)?
+                .iter()
+                .map(|v| v.trim_matches('"').to_string())
+                .collect()
+        };
+        uid.extend(source.body_uid(url, request.body)?);
+
+        let fixed_asset = source.fixed_asset(url);
+        let mut rows = vec![];
+        for bal_paths in std::iter::once(source.bal_paths(url)).chain(source.extra_bal_paths(url).iter().copied()) {
+            if bal_paths.is_empty() {
+                continue;
+            }
+            let json_value = request
+                .response
+                .json_values(bal_paths)
+                .map_err(SourceError::GetJsonValueFail)?;
+
+            if json_value.len() % bal_paths.len() != 0 {
+                return Err(SourceError::InvalidJsonValueSize);
+            }
+
+            let size = json_value.len() / bal_paths.len();
+            let first = fixed_asset.is_none() as usize;
+            for j in 0..size {
+                let asset = match fixed_asset {
+                    Some(asset) => asset.to_string(),
+                    None => json_value[j].trim_matches('"').to_ascii_uppercase(),
+                };
+                rows.push(BalanceRow {
+                    asset,
+                    amounts: (first..bal_paths.len())
+                        .map(|k| json_value[size * k + j].trim_matches('"').to_string())
+                        .collect(),
+                    paths: &bal_paths[first..],
+                });
+            }
+        }
+
+        // The rows of a paired balance request belong to the account identified before it,
+        // those of any other request to the account it identifies, if any
+        let id_urls = source.id_urls();
+        let is_id = id_urls.contains(&url);
+        if id_urls.is_empty() || is_id {
+            account = None;
+        }
+        let key = source.dedupe_key(url, &uid, &rows)?;
+        if let Some(key) = &key {
+            uids.push(key.clone());
+        }
+        if let Some(accounts) = accounts.as_deref_mut() {
+            // An identifying request of no key still opens an account, named by its index
+            if let Some(account_id) = key.or_else(|| is_id.then(|| format!("#{}", i))) {
+                accounts.push(AccountEntry {
+                    source: source.key().to_string(),
+                    account_id,
+                    balances: BTreeMap::new(),
+                });
+                account = Some(accounts.len() - 1);
+            }
+        }
+
+        for row in rows {
+            let Some(asset) = source.normalize_asset(row.asset) else {
+                continue;
+            };
+            let amounts = row
+                .amounts
+                .iter()
+                .zip(row.paths)
+                .map(|(v, path)| parse_balance(v, &asset, path, mode))
+                .collect::<Result<Vec<_>, _>>()?;
+            let bal = source
+                .combine(&amounts)
+                .ok_or_else(|| SourceError::BalanceOverflow(asset.clone()))?;
+            if let Some(accounts) = accounts.as_deref_mut() {
+                let account = account.ok_or_else(|| {
+                    SourceError::MissingAccountId(format!("{} request {}: {}", source.key(), i, asset))
+                })?;
+                add_balance(&mut accounts[account].balances, &asset, &bal)?;
+            }
+            add_balance(asset_bals, &asset, &bal)?;
+        }
+    }
+
+    // Is the account duplicate?
+    let mut seen = HashSet::new();
+    if uids.iter().any(|x| !seen.insert(x)) {
+        return Err(SourceError::DuplicateAccount(source.key().to_string()));
+    }
+
+    Ok((timestamp, timestamp_max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn source(key: &str) -> &'static (dyn DataSource + Sync) {
+        *REGISTRY.iter().find(|s| s.key() == key).unwrap()
+    }
+
+    fn requests(requests: &[(&'static str, Value)]) -> Vec<AttestedRequest<'static, Value>> {
+        requests
+            .iter()
+            .map(|(url, response)| AttestedRequest {
+                url,
+                body: "",
+                response: response.clone(),
+            })
+            .collect()
+    }
+
+    fn accounts_of(key: &str, reqs: &[(&'static str, Value)]) -> Result<Vec<AccountEntry>, SourceError> {
+        let mut accounts = vec![];
+        extract(
+            source(key),
+            &requests(reqs),
+            0,
+            BalanceParseMode::Strict,
+            &mut BTreeMap::new(),
+            Some(&mut accounts),
+        )?;
+        Ok(accounts)
+    }
+
+    #[test]
+    fn selects_the_path_subset() {
+        let value = json!({"uid": 7, "data": [{"details": [{"ccy": "BTC"}, {"ccy": "ETH"}]}, {"details": []}]});
+        assert_eq!(value.json_values(&["$.uid"]).unwrap(), vec!["7"]);
+        assert_eq!(
+            value.json_values(&["$.data[*].details[*].ccy"]).unwrap(),
+            vec!["\"BTC\"", "\"ETH\""]
+        );
+        assert_eq!(
+            json!([{"a": 1}, {"a": 2}]).json_values(&["$.[*].a"]).unwrap(),
+            vec!["1", "2"]
+        );
+        assert!(value.json_values(&["uid"]).is_err());
+    }
+
+    #[test]
+    fn assigns_paired_balances_to_the_account_before_them() {
+        let accounts = accounts_of(
+            "okxFunding",
+            &[
+                (
+                    "https://www.okx.com/api/v5/account/config",
+                    json!({"data": [{"uid": "1"}]}),
+                ),
+                (
+                    "https://www.okx.com/api/v5/asset/balances",
+                    json!({"data": [{"ccy": "btc", "bal": "1.5"}]}),
+                ),
+                (
+                    "https://www.okx.com/api/v5/account/config",
+                    json!({"data": [{"uid": "2"}]}),
+                ),
+                (
+                    "https://www.okx.com/api/v5/asset/balances",
+                    json!({"data": [{"ccy": "ETH", "bal": "2"}]}),
+                ),
+            ],
+        )
+        .unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].account_id, "1");
+        assert_eq!(accounts[0].balances["BTC"], "1.5".parse().unwrap());
+        assert_eq!(accounts[1].account_id, "2");
+        assert_eq!(accounts[1].balances["ETH"], "2".parse().unwrap());
+    }
+
+    #[test]
+    fn rejects_unpaired_requests() {
+        let balance = ("https://www.okx.com/api/v5/asset/balances", json!({"data": []}));
+        let config = (
+            "https://www.okx.com/api/v5/account/config",
+            json!({"data": [{"uid": "1"}]}),
+        );
+        assert!(matches!(
+            accounts_of("okxFunding", &[balance.clone(), config.clone()]),
+            Err(SourceError::InvalidRequestOrder)
+        ));
+        assert!(matches!(
+            accounts_of("okxFunding", &[config.clone(), balance.clone(), config]),
+            Err(SourceError::InvalidRequestLength)
+        ));
+    }
+
+    #[test]
+    fn identifies_unified_accounts_by_uid_or_positions() {
+        let balance = (
+            "https://papi.binance.com/papi/v1/balance?timestamp=2",
+            json!([{
+                "asset": "USDT", "crossMarginFree": "5", "crossMarginLocked": "0", "crossMarginBorrowed": "1",
+                "crossMarginInterest": "0", "umWalletBalance": "2", "umUnrealizedPNL": "0",
+                "cmWalletBalance": "0", "cmUnrealizedPNL": "0"
+            }]),
+        );
+        let accounts = accounts_of(
+            "binanceUnified",
+            &[
+                (
+                    "https://papi.binance.com/papi/v1/um/positionRisk?timestamp=1",
+                    json!([{"symbol": "btcusdt", "entryPrice": "100"}]),
+                ),
+                balance.clone(),
+                (
+                    "https://papi.binance.com/papi/v1/um/positionRisk?timestamp=1",
+                    json!([]),
+                ),
+                balance.clone(),
+                ("https://api.binance.com/api/v3/account?timestamp=1", json!({"uid": 9})),
+                balance,
+            ],
+        )
+        .unwrap();
+        let ids: Vec<&str> = accounts.iter().map(|a| a.account_id.as_str()).collect();
+        assert_eq!(ids, ["BTCUSDT:100", "#2", "9"]);
+        assert!(accounts.iter().all(|a| a.balances["USDT"] == "6".parse().unwrap()));
+    }
+
+    #[test]
+    fn rejects_duplicate_accounts() {
+        let spot = (
+            "https://api.binance.com/api/v3/account?timestamp=1",
+            json!({"uid": 1, "balances": []}),
+        );
+        assert!(matches!(
+            accounts_of("binanceSpot", &[spot.clone(), spot]),
+            Err(SourceError::DuplicateAccount(_))
+        ));
+    }
+}
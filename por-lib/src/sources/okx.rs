@@ -0,0 +1,103 @@
+use super::{DataSource, SourceError};
+
+const OKX_CONFIG_URL: &str = "https://www.okx.com/api/v5/account/config";
+const OKX_TRADING_BALANCE_URL: &str = "https://www.okx.com/api/v5/account/balance";
+const OKX_FUNDING_BALANCE_URL: &str = "https://www.okx.com/api/v5/asset/balances";
+const OKX_TICKERS_URL: &str = "https://www.okx.com/api/v5/market/tickers?instType=SPOT";
+
+// The balance responses carry no account id, so each one is preceded by the account config.
+pub struct OkxTrading;
+
+impl DataSource for OkxTrading {
+    fn key(&self) -> &'static str {
+        "okxTrading"
+    }
+    fn venue(&self) -> &'static str {
+        "okx"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[OKX_CONFIG_URL, OKX_TRADING_BALANCE_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            OKX_CONFIG_URL => &["$.data[*].uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            OKX_TRADING_BALANCE_URL => &["$.data[*].details[*].ccy", "$.data[*].details[*].eq"],
+            _ => &[],
+        }
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[OKX_CONFIG_URL]
+    }
+}
+
+pub struct OkxFunding;
+
+impl DataSource for OkxFunding {
+    fn key(&self) -> &'static str {
+        "okxFunding"
+    }
+    fn venue(&self) -> &'static str {
+        "okx"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[OKX_CONFIG_URL, OKX_FUNDING_BALANCE_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn uid_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            OKX_CONFIG_URL => &["$.data[*].uid"],
+            _ => &[],
+        }
+    }
+    fn bal_paths(&self, url: &str) -> &'static [&'static str] {
+        match url {
+            OKX_FUNDING_BALANCE_URL => &["$.data[*].ccy", "$.data[*].bal"],
+            _ => &[],
+        }
+    }
+    fn id_urls(&self) -> &'static [&'static str] {
+        &[OKX_CONFIG_URL]
+    }
+}
+
+// Not a venue: the last prices of all spot instruments, reported as symbol => price
+pub struct OkxTickerPrice;
+
+impl DataSource for OkxTickerPrice {
+    fn key(&self) -> &'static str {
+        "okxTickerPrice"
+    }
+    fn venue(&self) -> &'static str {
+        "okx"
+    }
+    fn urls(&self) -> &'static [&'static str] {
+        &[OKX_TICKERS_URL]
+    }
+    fn timestamp_in_url(&self) -> bool {
+        false
+    }
+    fn bal_paths(&self, _url: &str) -> &'static [&'static str] {
+        &["$.data[*].instId", "$.data[*].last"]
+    }
+    // BTC-USDT => BTCUSDT
+    fn normalize_asset(&self, asset: String) -> Option<String> {
+        Some(asset.replace('-', ""))
+    }
+    // One response, so every symbol has one price
+    fn check_requests(&self, len: usize) -> Result<(), SourceError> {
+        if len != 1 {
+            return Err(SourceError::InvalidRequestLength);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+use super::{add_balance, parse_address, AttestedRequest, Response, SourceError};
+use crate::account::AccountEntry;
+use crate::decimal::{Decimal, MAX_SCALE};
+
+/// Selector of ERC-20 `balanceOf(address)`.
+const BALANCE_OF_SELECTOR: &str = "0x70a08231";
+
+/// An ERC-20 token of an `OnchainConfig`.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenConfig {
+    pub asset: String,
+    pub address: String,
+    pub decimals: u32,
+}
+
+fn default_native_decimals() -> u32 {
+    18
+}
+
+/// EVM wallets read over JSON-RPC, reported under `onchain:<chain>`.
+/// The attestation is passed under the same key. Committed by `ProgramConfig::onchain_hash`.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OnchainConfig {
+    /// Chain name, e.g. `ethereum`.
+    pub chain: String,
+    /// Allowlisted RPC endpoints the requests must start with.
+    pub rpc_urls: Vec<String>,
+    /// Block every balance must be read at.
+    pub block_number: u64,
+    /// Asset of `eth_getBalance`, e.g. `ETH`.
+    pub native_asset: String,
+    #[serde(default = "default_native_decimals")]
+    pub native_decimals: u32,
+    /// Tokens accepted in `eth_call balanceOf` requests.
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+}
+
+impl OnchainConfig {
+    /// Key of the attestation map and source of the balances, `onchain:<chain>`.
+    pub fn key(&self) -> String {
+        format!("onchain:{}", self.chain)
+    }
+}
+
+fn invalid_request(msg: &str) -> SourceError {
+    SourceError::InvalidRpcRequest(msg.to_string())
+}
+
+/// Parses a JSON-RPC quantity, e.g. `0x1b4`.
+fn parse_quantity(value: &str) -> Option<u128> {
+    let hex = value.strip_prefix("0x")?;
+    if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.trim_start_matches('0') {
+        "" => Some(0),
+        hex => u128::from_str_radix(hex, 16).ok(),
+    }
+}
+
+/// Parses a `balanceOf` return value, a 32-byte word.
+fn parse_word(value: &str) -> Option<u128> {
+    let hex = value.strip_prefix("0x")?;
+    if hex.len() != 64 {
+        return None;
+    }
+    parse_quantity(value)
+}
+
+/// `amount * 10^-decimals`; precision beyond `MAX_SCALE` is dropped.
+fn to_decimal(amount: u128, decimals: u32) -> Result<Decimal, SourceError> {
+    let overflow = || SourceError::BalanceOverflow(amount.to_string());
+    let mut amount = i128::try_from(amount).map_err(|_| overflow())?;
+    if decimals > MAX_SCALE {
+        amount /= 10i128.checked_pow(decimals - MAX_SCALE).ok_or_else(overflow)?;
+    }
+    Ok(Decimal::from_scaled(amount, decimals.min(MAX_SCALE)))
+}
+
+/// What one JSON-RPC request reads: the wallet, the asset and its decimals.
+struct BalanceQuery {
+    address: String,
+    asset: String,
+    decimals: u32,
+    token: bool,
+}
+
+/// Parses the body of a JSON-RPC request, which must read a configured asset at the pinned block.
+fn parse_query(body: &str, chain: &OnchainConfig) -> Result<BalanceQuery, SourceError> {
+    let body: Value = serde_json::from_str(body).map_err(|e| invalid_request(&e.to_string()))?;
+    let method = body["method"].as_str().unwrap_or_default();
+    let params = body["params"].as_array().ok_or_else(|| invalid_request("params"))?;
+    if params.len() != 2 {
+        return Err(invalid_request("params"));
+    }
+
+    // pin the block
+    let block = params[1].as_str().and_then(parse_quantity);
+    if block != Some(chain.block_number as u128) {
+        return Err(SourceError::BlockNumberMismatch(format!(
+            "{} at {}, expected {}",
+            chain.chain, params[1], chain.block_number
+        )));
+    }
+
+    match method {
+        "eth_getBalance" => Ok(BalanceQuery {
+            address: params[0]
+                .as_str()
+                .and_then(parse_address)
+                .ok_or_else(|| invalid_request("address"))?,
+            asset: chain.native_asset.to_ascii_uppercase(),
+            decimals: chain.native_decimals,
+            token: false,
+        }),
+        "eth_call" => {
+            let to = params[0]["to"].as_str().unwrap_or_default();
+            let token = chain
+                .tokens
+                .iter()
+                .find(|t| t.address.eq_ignore_ascii_case(to))
+                .ok_or_else(|| invalid_request(to))?;
+            let data = params[0]["data"]
+                .as_str()
+                .or(params[0]["input"].as_str())
+                .unwrap_or_default();
+            let arg = data
+                .strip_prefix(BALANCE_OF_SELECTOR)
+                .filter(|a| a.len() == 64 && a[..24].bytes().all(|b| b == b'0'))
+                .ok_or_else(|| invalid_request(data))?;
+            Ok(BalanceQuery {
+                address: parse_address(&format!("0x{}", &arg[24..])).ok_or_else(|| invalid_request(data))?,
+                asset: token.asset.to_ascii_uppercase(),
+                decimals: token.decimals,
+                token: true,
+            })
+        }
+        _ => Err(invalid_request(method)),
+    }
+}
+
+/// Reads one balance per attested `eth_getBalance` / `eth_call balanceOf` request of `chain`,
+/// returning the balances per asset. Each address is an account, appended to `accounts` in address order.
+pub fn extract_chain<R: Response>(
+    chain: &OnchainConfig,
+    requests: &[AttestedRequest<R>],
+    accounts: &mut Vec<AccountEntry>,
+) -> Result<BTreeMap<String, Decimal>, SourceError> {
+    if requests.is_empty() {
+        return Err(SourceError::InvalidRequestLength);
+    }
+
+    let mut asset_bals: BTreeMap<String, Decimal> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    let mut wallets: BTreeMap<String, BTreeMap<String, Decimal>> = BTreeMap::new();
+    for request in requests {
+        if !chain.rpc_urls.iter().any(|u| request.url.starts_with(u.as_str())) {
+            return Err(SourceError::InvalidRequestUrl);
+        }
+        let query = parse_query(request.body, chain)?;
+
+        // Is the address duplicate?
+        if !seen.insert((query.address.clone(), query.asset.clone())) {
+            return Err(SourceError::DuplicateAccount(format!(
+                "{} {}",
+                query.address, query.asset
+            )));
+        }
+
+        let result = request
+            .response
+            .json_values(&["$.result"])
+            .map_err(SourceError::GetJsonValueFail)?;
+        if result.len() != 1 {
+            return Err(SourceError::InvalidJsonValueSize);
+        }
+        let result = result[0].trim_matches('"');
+        let amount = if query.token {
+            parse_word(result)
+        } else {
+            parse_quantity(result)
+        }
+        .ok_or_else(|| SourceError::ParseBalanceFailed(format!("{} {}: {:?}", query.asset, query.address, result)))?;
+        let bal = to_decimal(amount, query.decimals)?;
+        add_balance(wallets.entry(query.address).or_default(), &query.asset, &bal)?;
+        add_balance(&mut asset_bals, &query.asset, &bal)?;
+    }
+    accounts.extend(wallets.into_iter().map(|(address, balances)| AccountEntry {
+        source: chain.key(),
+        account_id: address,
+        balances,
+    }));
+
+    Ok(asset_bals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const WALLET: &str = "0x00000000000000000000000000000000000000ab";
+    const USDT: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+
+    fn chain() -> OnchainConfig {
+        OnchainConfig {
+            chain: "ethereum".to_string(),
+            rpc_urls: vec!["https://rpc.example".to_string()],
+            block_number: 0x10,
+            native_asset: "eth".to_string(),
+            native_decimals: 18,
+            tokens: vec![TokenConfig {
+                asset: "usdt".to_string(),
+                address: USDT.to_string(),
+                decimals: 6,
+            }],
+        }
+    }
+
+    fn get_balance(address: &str, block: &str) -> String {
+        json!({"method": "eth_getBalance", "params": [address, block]}).to_string()
+    }
+
+    fn balance_of(to: &str, address: &str) -> String {
+        let data = format!("{}{:0>64}", BALANCE_OF_SELECTOR, address.trim_start_matches("0x"));
+        json!({"method": "eth_call", "params": [{"to": to, "data": data}, "0x10"]}).to_string()
+    }
+
+    #[test]
+    fn parses_quantities_and_words() {
+        assert_eq!(parse_quantity("0x1b4"), Some(0x1b4));
+        assert_eq!(parse_quantity("0x0"), Some(0));
+        assert_eq!(parse_quantity("0x"), None);
+        assert_eq!(parse_quantity("1b4"), None);
+        assert_eq!(parse_quantity("0xzz"), None);
+        assert_eq!(parse_word(&format!("0x{:064x}", 1000)), Some(1000));
+        assert_eq!(parse_word("0x3e8"), None);
+        // above u128
+        assert_eq!(parse_word(&format!("0x{:0<64}", 1)), None);
+    }
+
+    #[test]
+    fn parses_native_and_token_queries() {
+        let query = parse_query(
+            &get_balance("0x00000000000000000000000000000000000000AB", "0x10"),
+            &chain(),
+        )
+        .unwrap();
+        assert_eq!((query.address.as_str(), query.asset.as_str()), (WALLET, "ETH"));
+        assert_eq!((query.decimals, query.token), (18, false));
+
+        let query = parse_query(&balance_of(&USDT.to_ascii_lowercase(), WALLET), &chain()).unwrap();
+        assert_eq!((query.address.as_str(), query.asset.as_str()), (WALLET, "USDT"));
+        assert_eq!((query.decimals, query.token), (6, true));
+    }
+
+    #[test]
+    fn rejects_queries_off_the_pinned_block() {
+        for block in ["0x11", "latest", "0xf"] {
+            assert!(matches!(
+                parse_query(&get_balance(WALLET, block), &chain()),
+                Err(SourceError::BlockNumberMismatch(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_unconfigured_tokens_and_methods() {
+        assert!(matches!(
+            parse_query(
+                &balance_of("0x0000000000000000000000000000000000000001", WALLET),
+                &chain()
+            ),
+            Err(SourceError::InvalidRpcRequest(_))
+        ));
+        let body = json!({"method": "eth_getCode", "params": [WALLET, "0x10"]}).to_string();
+        assert!(matches!(
+            parse_query(&body, &chain()),
+            Err(SourceError::InvalidRpcRequest(_))
+        ));
+    }
+
+    #[test]
+    fn extracts_one_account_per_wallet() {
+        let requests = [
+            (get_balance(WALLET, "0x10"), json!({"result": "0xde0b6b3a7640000"})),
+            (
+                balance_of(USDT, WALLET),
+                json!({"result": format!("0x{:064x}", 2_500_000)}),
+            ),
+        ];
+        let requests: Vec<_> = requests
+            .iter()
+            .map(|(body, response)| AttestedRequest {
+                url: "https://rpc.example",
+                body,
+                response: response.clone(),
+            })
+            .collect();
+        let mut accounts = vec![];
+        let bals = extract_chain(&chain(), &requests, &mut accounts).unwrap();
+        assert_eq!(bals["ETH"], "1".parse().unwrap());
+        assert_eq!(bals["USDT"], "2.5".parse().unwrap());
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].source, "onchain:ethereum");
+        assert_eq!(accounts[0].account_id, WALLET);
+
+        // the same balance read twice
+        let twice = [requests[0].url, requests[0].url].map(|url| AttestedRequest {
+            url,
+            body: requests[0].body,
+            response: requests[0].response.clone(),
+        });
+        assert!(matches!(
+            extract_chain(&chain(), &twice, &mut vec![]),
+            Err(SourceError::DuplicateAccount(_))
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+use alloy_sol_types::{private::U256, sol, SolValue};
+use por_lib::attestation::FreshnessConfig;
+use por_lib::decimal::Decimal;
+use std::collections::BTreeMap;
+
+use crate::errors::{ZkErrorCode, ZktlsError};
+use crate::structs::{
+    AttestationMetaStruct, LiabilityPublicValuesStruct, PublicValuesStruct, SolvencyPublicValuesStruct,
+};
+use crate::zkerr;
+
+/// Fixed-point scale of the ABI-encoded balances: `balance * 10^18`.
+pub const ABI_DECIMALS: u32 = 18;
+
+sol! {
+    /// One entry of `asset_balance`, `balance` scaled by `10^18`.
+    struct AssetBalanceAbi {
+        string source;
+        string asset;
+        uint256 balance;
+    }
+
+    /// The block the balances of `chain` were read at.
+    struct ChainBlockAbi {
+        string chain;
+        uint64 blockNumber;
+    }
+
+    /// The freshness bounds checked, in ms. A bound of `type(uint64).max` is not checked;
+    /// without `freshness` neither is and `referenceTime` is 0.
+    struct FreshnessAbi {
+        uint64 referenceTime;
+        uint64 maxAge;
+        uint64 maxSpread;
+    }
+
+    /// `threshold` scaled by `10^18`, `asset` is `USD` for the USD total.
+    struct ThresholdAbi {
+        string asset;
+        uint256 threshold;
+        bool met;
+    }
+
+    /// The public values as a Solidity contract decodes them:
+    /// `abi.decode(publicValues, (PublicValuesAbi))`. `version` is the layout, see `PUBLIC_VALUES_VERSION`.
+    struct PublicValuesAbi {
+        string version;
+        string projectId;
+        uint64 timestampFrom;
+        uint64 timestampTo;
+        FreshnessAbi freshness;
+        AssetBalanceAbi[] balances;
+        ChainBlockAbi[] blocks;
+        bytes32 onchainHash;
+        AssetAmountAbi[] usdValues;
+        uint256 usdTotal;
+        AssetAmountAbi[] usdPrices;
+        ThresholdAbi[] thresholds;
+        bytes32 attestorAllowlistHash;
+        uint64 minAttestors;
+        bytes32 classificationHash;
+        string disclosureScope;
+        bytes32 detailHash;
+        bytes32 accountRoot;
+        uint64 accountCount;
+        int16 status;
+    }
+
+    /// One asset total, `amount` scaled by `10^18`.
+    struct AssetAmountAbi {
+        string asset;
+        uint256 amount;
+    }
+
+    /// `abi.decode(publicValues, (LiabilityPublicValuesAbi))`.
+    struct LiabilityPublicValuesAbi {
+        string version;
+        string projectId;
+        bytes32 liabilityRoot;
+        uint64 userCount;
+        AssetAmountAbi[] liabilities;
+        int16 status;
+    }
+}
+
+sol! {
+    /// `reserveRatio` scaled by `10^18`, `type(uint256).max` if there are no liabilities.
+    struct AssetSolvencyAbi {
+        string asset;
+        uint256 reserveRatio;
+        bool solvent;
+    }
+
+    /// `abi.decode(publicValues, (SolvencyPublicValuesAbi))`.
+    struct SolvencyPublicValuesAbi {
+        string version;
+        string projectId;
+        uint64 timestampFrom;
+        uint64 timestampTo;
+        FreshnessAbi freshness;
+        bytes32 liabilityRoot;
+        uint64 userCount;
+        AssetSolvencyAbi[] solvency;
+        ChainBlockAbi[] blocks;
+        bytes32 onchainHash;
+        bytes32 attestorAllowlistHash;
+        uint64 minAttestors;
+        int16 status;
+    }
+}
+
+/// `bal` scaled by `10^ABI_DECIMALS`. Fails on negative amounts and amounts that do not fit.
+fn to_uint256(bal: &Decimal) -> Result<U256, ZktlsError> {
+    bal.to_scaled(ABI_DECIMALS)
+        .and_then(|v| u128::try_from(v).ok())
+        .map(U256::from)
+        .ok_or_else(|| zkerr!(ZkErrorCode::BalanceOverflow, format!("{} is no uint256 amount", bal)))
+}
+
+fn timestamp_from(attestation_meta: &[AttestationMetaStruct]) -> u64 {
+    attestation_meta
+        .iter()
+        .map(|am| am.timestamp as u64)
+        .min()
+        .unwrap_or_default()
+}
+
+fn timestamp_to(attestation_meta: &[AttestationMetaStruct]) -> u64 {
+    attestation_meta
+        .iter()
+        .map(|am| am.timestamp_max as u64)
+        .max()
+        .unwrap_or_default()
+}
+
+fn to_amounts(amounts: &BTreeMap<String, Decimal>) -> Result<Vec<AssetAmountAbi>, ZktlsError> {
+    amounts
+        .iter()
+        .map(|(asset, amount)| {
+            Ok(AssetAmountAbi {
+                asset: asset.clone(),
+                amount: to_uint256(amount)?,
+            })
+        })
+        .collect()
+}
+
+fn to_freshness(freshness: &Option<FreshnessConfig>) -> FreshnessAbi {
+    let to_u64 = |ms: Option<u128>| ms.map_or(u64::MAX, |ms| u64::try_from(ms).unwrap_or(u64::MAX));
+    match freshness {
+        Some(f) => FreshnessAbi {
+            referenceTime: to_u64(Some(f.reference_time)),
+            maxAge: to_u64(f.max_age),
+            maxSpread: to_u64(f.max_spread),
+        },
+        None => FreshnessAbi {
+            referenceTime: 0,
+            maxAge: u64::MAX,
+            maxSpread: u64::MAX,
+        },
+    }
+}
+
+fn to_blocks(block_numbers: &BTreeMap<String, u64>) -> Vec<ChainBlockAbi> {
+    block_numbers
+        .iter()
+        .map(|(chain, block)| ChainBlockAbi {
+            chain: chain.clone(),
+            blockNumber: *block,
+        })
+        .collect()
+}
+
+fn to_bytes32(hex_str: &str) -> [u8; 32] {
+    hex::decode(hex_str)
+        .ok()
+        .and_then(|h| h.try_into().ok())
+        .unwrap_or_default()
+}
+
+/// ABI-encodes `pv`, balances in (source, asset) order.
+pub fn encode_public_values(pv: &PublicValuesStruct) -> Result<Vec<u8>, ZktlsError> {
+    let balances = pv
+        .asset_balance
+        .iter()
+        .flat_map(|(source, bals)| {
+            bals.iter().map(move |(asset, bal)| {
+                Ok(AssetBalanceAbi {
+                    source: source.clone(),
+                    asset: asset.clone(),
+                    balance: to_uint256(bal)?,
+                })
+            })
+        })
+        .collect::<Result<_, ZktlsError>>()?;
+
+    Ok(PublicValuesAbi {
+        version: pv.version.clone(),
+        projectId: pv.project_id.clone(),
+        timestampFrom: timestamp_from(&pv.attestation_meta),
+        timestampTo: timestamp_to(&pv.attestation_meta),
+        freshness: to_freshness(&pv.freshness),
+        balances,
+        blocks: to_blocks(&pv.block_numbers),
+        onchainHash: to_bytes32(&pv.onchain_hash).into(),
+        usdValues: to_amounts(&pv.usd_value)?,
+        usdTotal: pv.usd_total.as_ref().map(to_uint256).transpose()?.unwrap_or_default(),
+        usdPrices: to_amounts(&pv.usd_price)?,
+        thresholds: pv
+            .thresholds
+            .iter()
+            .map(|(asset, t)| {
+                Ok(ThresholdAbi {
+                    asset: asset.clone(),
+                    threshold: to_uint256(&t.threshold)?,
+                    met: t.met,
+                })
+            })
+            .collect::<Result<_, ZktlsError>>()?,
+        attestorAllowlistHash: to_bytes32(&pv.attestor_allowlist_hash).into(),
+        minAttestors: pv.min_attestors,
+        classificationHash: to_bytes32(&pv.classification_hash).into(),
+        disclosureScope: pv.disclosure_scope.clone(),
+        detailHash: to_bytes32(&pv.detail_hash).into(),
+        accountRoot: to_bytes32(&pv.account_root).into(),
+        accountCount: pv.account_count,
+        status: pv.status,
+    }
+    .abi_encode())
+}
+
+/// ABI-encodes `pv`, liabilities in asset order.
+pub fn encode_liability_public_values(pv: &LiabilityPublicValuesStruct) -> Result<Vec<u8>, ZktlsError> {
+    Ok(LiabilityPublicValuesAbi {
+        version: pv.version.clone(),
+        projectId: pv.project_id.clone(),
+        liabilityRoot: to_bytes32(&pv.liability_root).into(),
+        userCount: pv.user_count,
+        liabilities: to_amounts(&pv.liability_balance)?,
+        status: pv.status,
+    }
+    .abi_encode())
+}
+
+/// ABI-encodes `pv`, solvency in asset order. The absolute amounts are not encoded.
+pub fn encode_solvency_public_values(pv: &SolvencyPublicValuesStruct) -> Result<Vec<u8>, ZktlsError> {
+    Ok(SolvencyPublicValuesAbi {
+        version: pv.version.clone(),
+        projectId: pv.project_id.clone(),
+        timestampFrom: timestamp_from(&pv.attestation_meta),
+        timestampTo: timestamp_to(&pv.attestation_meta),
+        freshness: to_freshness(&pv.freshness),
+        liabilityRoot: to_bytes32(&pv.liability_root).into(),
+        userCount: pv.user_count,
+        solvency: pv
+            .solvency
+            .iter()
+            .map(|(asset, s)| {
+                Ok(AssetSolvencyAbi {
+                    asset: asset.clone(),
+                    reserveRatio: s.reserve_ratio.as_ref().map_or(Ok(U256::MAX), to_uint256)?,
+                    solvent: s.solvent,
+                })
+            })
+            .collect::<Result<_, ZktlsError>>()?,
+        blocks: to_blocks(&pv.block_numbers),
+        onchainHash: to_bytes32(&pv.onchain_hash).into(),
+        attestorAllowlistHash: to_bytes32(&pv.attestor_allowlist_hash).into(),
+        minAttestors: pv.min_attestors,
+        status: pv.status,
+    }
+    .abi_encode())
+}
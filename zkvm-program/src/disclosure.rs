@@ -0,0 +1,153 @@
+use por_lib::classification::{group, summarize, BalanceOverflow};
+use por_lib::decimal::Decimal;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::errors::{ZkErrorCode, ZktlsError};
+use crate::sources::add_balance;
+use crate::structs::{DisclosureScope, ProgramConfig, PublicValuesStruct, ThresholdStruct};
+use crate::{ensure_zk, zkerr};
+
+/// Category of the assets the classification leaves out, `per-category` scope.
+const OTHER_CATEGORY: &str = "OTHER";
+
+/// Source of the cross-source balances.
+const TOTAL_SOURCE: &str = "total";
+
+/// Key of the `usd_total` threshold.
+const USD_THRESHOLD: &str = "USD";
+
+/// Everything verified, before the disclosure scope is applied.
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct Detail {
+    pub asset_balance: BTreeMap<String, BTreeMap<String, Decimal>>, // source => { asset => balance }
+    pub usd_price: BTreeMap<String, Decimal>,                       // asset => USD price
+    pub usd_value: BTreeMap<String, Decimal>,                       // asset => USD value
+}
+
+impl Detail {
+    /// asset => balance over all sources.
+    pub fn reserves(&self) -> Result<BTreeMap<String, Decimal>, ZktlsError> {
+        let mut reserves: BTreeMap<String, Decimal> = BTreeMap::new();
+        for asset_bals in self.asset_balance.values() {
+            for (asset, bal) in asset_bals {
+                add_balance(&mut reserves, asset, bal)?;
+            }
+        }
+        Ok(reserves)
+    }
+
+    /// Hex SHA-256 of the JSON `[salt, detail]`, so the detail can be opened later.
+    fn commitment(&self, salt: &str) -> String {
+        hex::encode(Sha256::digest(serde_json::to_vec(&(salt, self)).unwrap()))
+    }
+}
+
+impl From<BalanceOverflow> for ZktlsError {
+    fn from(e: BalanceOverflow) -> Self {
+        ZktlsError::new(ZkErrorCode::BalanceOverflow, e.0)
+    }
+}
+
+/// Commits what the disclosure scope allows of `detail`, and a commitment to all of it salted with `salt`.
+pub fn app_disclosure(
+    pv: &mut PublicValuesStruct,
+    detail: &Detail,
+    salt: &str,
+    program_config: &ProgramConfig,
+) -> Result<(), ZktlsError> {
+    let categories = program_config
+        .asset_categories()
+        .map_err(|asset| zkerr!(ZkErrorCode::InvalidClassification, asset))?;
+    let scope = program_config.disclosure;
+    let valued = program_config.valuation.is_some();
+    ensure_zk!(
+        valued || scope != DisclosureScope::UsdTotal,
+        zkerr!(ZkErrorCode::InvalidDisclosureScope, scope.as_str().to_string())
+    );
+
+    ensure_zk!(
+        program_config.thresholds.is_some() || scope != DisclosureScope::ThresholdOnly,
+        zkerr!(ZkErrorCode::InvalidDisclosureScope, scope.as_str().to_string())
+    );
+
+    let usd_value = summarize(&detail.usd_value, &categories, None)?;
+    // Net of the negative values, which only the displayed summaries leave out
+    let usd_total = if valued {
+        Some(
+            Decimal::checked_sum(detail.usd_value.values())
+                .ok_or_else(|| zkerr!(ZkErrorCode::BalanceOverflow, USD_THRESHOLD.to_string()))?,
+        )
+    } else {
+        None
+    };
+
+    // Thresholds are checked against the cross-source balances, whatever the scope
+    if let Some(thresholds) = &program_config.thresholds {
+        let reserves = group(&detail.reserves()?, &categories, None)?;
+        for (asset, threshold) in &thresholds.assets {
+            // Assets as grouped: uppercase, classified ones under their category
+            let asset = asset.to_ascii_uppercase();
+            let category = categories.get(&asset).unwrap_or(&asset);
+            ensure_zk!(
+                *category == asset,
+                zkerr!(
+                    ZkErrorCode::InvalidThreshold,
+                    format!("{} is reported under {}", asset, category)
+                )
+            );
+            ensure_zk!(
+                asset != USD_THRESHOLD && !pv.thresholds.contains_key(&asset),
+                zkerr!(ZkErrorCode::InvalidThreshold, asset)
+            );
+            let reserve = reserves.get(&asset).copied().unwrap_or_default();
+            let met = reserve >= *threshold;
+            pv.thresholds.insert(
+                asset,
+                ThresholdStruct {
+                    threshold: *threshold,
+                    met,
+                },
+            );
+        }
+        if let Some(threshold) = thresholds.usd {
+            let usd_total =
+                usd_total.ok_or_else(|| zkerr!(ZkErrorCode::InvalidDisclosureScope, USD_THRESHOLD.to_string()))?;
+            let met = usd_total >= threshold;
+            pv.thresholds
+                .insert(USD_THRESHOLD.to_string(), ThresholdStruct { threshold, met });
+        }
+    }
+    if scope != DisclosureScope::ThresholdOnly {
+        pv.usd_total = usd_total;
+    }
+
+    match scope {
+        DisclosureScope::PerAssetPerSource => {
+            pv.asset_balance = detail
+                .asset_balance
+                .iter()
+                .map(|(source, asset_bals)| Ok((source.clone(), summarize(asset_bals, &categories, None)?)))
+                .collect::<Result<_, ZktlsError>>()?;
+            pv.usd_price = detail.usd_price.clone();
+            pv.usd_value = usd_value;
+        }
+        DisclosureScope::TotalPerAsset => {
+            let reserves = summarize(&detail.reserves()?, &categories, None)?;
+            pv.asset_balance = BTreeMap::from([(TOTAL_SOURCE.to_string(), reserves)]);
+            pv.usd_price = detail.usd_price.clone();
+            pv.usd_value = usd_value;
+        }
+        DisclosureScope::PerCategory => {
+            let reserves = summarize(&detail.reserves()?, &categories, Some(OTHER_CATEGORY))?;
+            pv.asset_balance = BTreeMap::from([(TOTAL_SOURCE.to_string(), reserves)]);
+            pv.usd_value = summarize(&detail.usd_value, &categories, Some(OTHER_CATEGORY))?;
+        }
+        DisclosureScope::UsdTotal | DisclosureScope::ThresholdOnly => {}
+    }
+    pv.disclosure_scope = scope.as_str().to_string();
+    pv.detail_hash = detail.commitment(salt);
+
+    Ok(())
+}
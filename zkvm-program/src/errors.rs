@@ -14,6 +14,29 @@ pub enum ZkErrorCode {
     InvalidRequestOrder,
     InvalidRequestUrl,
     DuplicateAccount,
+    ParseBalanceFailed,
+    MissingLiabilityData,
+    ParseLiabilityData,
+    InvalidUserHash,
+    NegativeLiability,
+    BalanceOverflow,
+    AttestationTooOld,
+    TimestampSpreadTooLarge,
+    UnknownAttestor,
+    InsufficientAttestors,
+    InvalidRpcRequest,
+    BlockNumberMismatch,
+    InvalidRequestBody,
+    InvalidClassification,
+    MissingPriceData,
+    PriceDeviation,
+    InvalidDisclosureScope,
+    InvalidSalt,
+    InvalidThreshold,
+    MissingAccountId,
+    TimestampInFuture,
+    DuplicateChain,
+    MissingFreshness,
 }
 
 pub struct ZktlsError {
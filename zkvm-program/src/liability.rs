@@ -0,0 +1,42 @@
+use por_lib::liability::{LiabilityEntry, LiabilityError, LiabilityTree};
+use std::collections::HashMap;
+
+use crate::errors::{ZkErrorCode, ZktlsError};
+use crate::structs::LiabilityPublicValuesStruct;
+use crate::zkerr;
+
+impl From<LiabilityError> for ZktlsError {
+    fn from(e: LiabilityError) -> Self {
+        let code = match e {
+            LiabilityError::NegativeLiability(..) => ZkErrorCode::NegativeLiability,
+            LiabilityError::DuplicateUser(_) => ZkErrorCode::DuplicateAccount,
+            LiabilityError::InvalidUserHash(_) => ZkErrorCode::InvalidUserHash,
+            _ => ZkErrorCode::BalanceOverflow,
+        };
+        ZktlsError::new(code, e.to_string())
+    }
+}
+
+/// Builds the Merkle sum tree of the `__liabilities__` input.
+pub fn build_liability_tree(attestations: &HashMap<String, String>) -> Result<LiabilityTree, ZktlsError> {
+    let data = attestations
+        .get("__liabilities__")
+        .ok_or_else(|| zkerr!(ZkErrorCode::MissingLiabilityData))?;
+    let entries: Vec<LiabilityEntry> =
+        serde_json::from_str(data).map_err(|e| zkerr!(ZkErrorCode::ParseLiabilityData, e.to_string()))?;
+
+    Ok(LiabilityTree::build(&entries)?)
+}
+
+/// Commits the root and per-asset totals of the liability tree.
+pub fn app_liability(
+    pv: &mut LiabilityPublicValuesStruct,
+    attestations: &HashMap<String, String>,
+) -> Result<(), ZktlsError> {
+    let tree = build_liability_tree(attestations)?;
+    pv.liability_root = tree.root_hex();
+    pv.user_count = tree.user_count();
+    pv.liability_balance = tree.totals();
+
+    Ok(())
+}
@@ -2,684 +2,233 @@
 sp1_zkvm::entrypoint!(main);
 
 use anyhow::Result;
-use sp1_zkvm::io::commit;
-use std::collections::{HashMap, HashSet};
-use zktls_att_verification::attestation_data::verify_attestation_data;
+use por_lib::account::AccountTree;
+use sp1_zkvm::io::{commit, commit_slice};
+use std::collections::HashMap;
 use zktls_att_verification::attestation_data::AttestationConfig;
 
+mod abi;
+mod disclosure;
+use disclosure::Detail;
 mod errors;
 use errors::{ZkErrorCode, ZktlsError};
+mod liability;
+mod solvency;
+mod sources;
 mod structs;
-use structs::{AttestationMetaStruct, PublicValuesStruct};
+mod valuation;
+use structs::{
+    DisclosureScope, LiabilityPublicValuesStruct, OutputMode, ProgramConfig, ProofKind, PublicValues,
+    PublicValuesStruct, SolvencyPublicValuesStruct,
+};
 
-const RISK_URL: &str = "https://papi.binance.com/papi/v1/um/positionRisk";
-const BALANCE_URL: &str = "https://papi.binance.com/papi/v1/balance";
-const SPOT_BALANCE_URL: &str = "https://api.binance.com/api/v3/account";
-const FEATURE_BALANCE_URL: &str = "https://fapi.binance.com/fapi/v3/balance";
+/// Version of the public values layout, bumped on every change of their encoding.
+const PUBLIC_VALUES_VERSION: &str = "0.2.0";
 
-const ASTER_SPOT_BALANCE_URL: &str = "https://sapi.asterdex.com/api/v1/account";
-const ASTER_FEATURE_BALANCE_URL: &str = "https://fapi.asterdex.com/fapi/v2/balance";
-
-const STABLE_COINS: &[&str] = &[
-    "USDT", "USDC", "FDUSD", "TUSD", "USDE", "XUSD", "USD1", "BFUSD", "USDP", "DAI", "USDF",
-];
-const EPSILON_VALUE: f64 = 0.00000000001;
-
-fn app_binance_spot(
-    pv: &mut AttestationMetaStruct,
-    attestation_data: &String,
-    attestation_config: &AttestationConfig,
-    asset_bals: &mut HashMap<String, f64>,
-) -> Result<(), ZktlsError> {
-    //
-    // 1. Verify
-    let mut attestation_config = attestation_config.clone();
-    attestation_config.url = vec![SPOT_BALANCE_URL.to_string()];
-    let attestation_config = serde_json::to_string(&attestation_config).unwrap();
-    let (attestation_data, _, messages) = verify_attestation_data(&attestation_data, &attestation_config)
-        .map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, e.to_string()))?;
-
-    pv.task_id = attestation_data.public_data[0].taskId.clone();
-    pv.report_tx_hash = attestation_data.public_data[0].reportTxHash.clone();
-    pv.attestor = attestation_data.public_data[0].attestor.clone();
-    pv.base_urls.push(SPOT_BALANCE_URL.to_string());
-
-    //
-    // 2. Do some valid checks
-    // In the vast majority of cases, it is legal. Data is extracted while the inspection is conducted.
-    let msg_len = messages.len();
-    let requests = attestation_data.public_data[0].attestation.request.clone();
-    let requests_len = requests.len();
-    ensure_zk!(requests_len == msg_len, zkerr!(ZkErrorCode::InvalidMessagesLength));
-
-    let mut i = 0;
-    let mut uid_paths = vec![];
-    uid_paths.push("$.uid");
-
-    let mut bal_paths = vec![];
-    bal_paths.push("$.balances[*].asset");
-    bal_paths.push("$.balances[*].free");
-    bal_paths.push("$.balances[*].locked");
-
-    pv.timestamp = u128::MAX;
-    let mut uids = vec![];
-    for request in requests {
-        let ts = request
-            .url
-            .split("timestamp=")
-            .nth(1)
-            .and_then(|s| s.split('&').next())
-            .filter(|s| !s.is_empty())
-            .ok_or(zkerr!(ZkErrorCode::CannotFoundTimestamp))?
-            .parse::<u128>()
-            .map_err(|_| zkerr!(ZkErrorCode::ParseTimestampFailed))?;
-        pv.timestamp = pv.timestamp.min(ts);
-
-        // check url
-        if !request.url.starts_with(SPOT_BALANCE_URL) {
-            return Err(zkerr!(ZkErrorCode::InvalidRequestUrl));
-        }
-
-        {
-            // uid
-            let json_value = messages[i]
-                .get_json_values(&uid_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(json_value.len() == 1, zkerr!(ZkErrorCode::InvalidJsonValueSize));
-
-            let uid = json_value[0].trim_matches('"').to_string();
-            uids.push(uid);
-        }
-
-        {
-            // balance
-            let json_value = messages[i]
-                .get_json_values(&bal_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(
-                json_value.len() % bal_paths.len() == 0,
-                zkerr!(ZkErrorCode::InvalidJsonValueSize)
-            );
-
-            let size = json_value.len() / bal_paths.len();
-            for j in 0..size {
-                let asset = json_value[j].trim_matches('"').to_ascii_uppercase();
-                let free: f64 = json_value[size + j].trim_matches('"').parse().unwrap_or(0.0);
-                let locked: f64 = json_value[size * 2 + j].trim_matches('"').parse().unwrap_or(0.0);
-                *asset_bals.entry(asset.to_string()).or_insert(0.0) += free + locked;
-            }
-        }
+/// Helper function
+fn set_meta(project_id: &mut String, attestations: &HashMap<String, String>) -> Result<(), ZktlsError> {
+    if let Some(meta) = attestations.get("__meta__") {
+        let meta: HashMap<String, String> =
+            serde_json::from_str(meta).map_err(|e| zkerr!(ZkErrorCode::ParseMetaData, e.to_string()))?;
 
-        i += 1;
+        *project_id = meta
+            .get("projectId")
+            .ok_or_else(|| zkerr!(ZkErrorCode::MissingProjectId))?
+            .to_owned();
     }
 
-    // Is the account duplicate?
-    let mut seen = HashSet::new();
-    ensure_zk!(
-        !uids.iter().any(|x| !seen.insert(x)),
-        zkerr!(ZkErrorCode::DuplicateAccount)
-    );
-
     Ok(())
 }
 
-fn app_binance_future(
-    pv: &mut AttestationMetaStruct,
-    attestation_data: &String,
-    attestation_config: &AttestationConfig,
-    asset_bals: &mut HashMap<String, f64>,
-) -> Result<(), ZktlsError> {
-    //
-    // 1. Verify
-    let mut attestation_config = attestation_config.clone();
-    attestation_config.url = vec![FEATURE_BALANCE_URL.to_string()];
-    let attestation_config = serde_json::to_string(&attestation_config).unwrap();
-    let (attestation_data, _, messages) = verify_attestation_data(&attestation_data, &attestation_config)
-        .map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, e.to_string()))?;
-
-    pv.task_id = attestation_data.public_data[0].taskId.clone();
-    pv.report_tx_hash = attestation_data.public_data[0].reportTxHash.clone();
-    pv.attestor = attestation_data.public_data[0].attestor.clone();
-    pv.base_urls.push(FEATURE_BALANCE_URL.to_string());
-
-    //
-    // 2. Do some valid checks
-    // In the vast majority of cases, it is legal. Data is extracted while the inspection is conducted.
-    let msg_len = messages.len();
-    let requests = attestation_data.public_data[0].attestation.request.clone();
-    let requests_len = requests.len();
-    ensure_zk!(requests_len == msg_len, zkerr!(ZkErrorCode::InvalidMessagesLength));
-
-    let mut i = 0;
-    let mut uid_paths = vec![];
-    uid_paths.push("$.[*].accountAlias");
-
-    let mut bal_paths = vec![];
-    bal_paths.push("$.[*].asset");
-    bal_paths.push("$.[*].balance");
-    bal_paths.push("$.[*].crossUnPnl");
-
-    pv.timestamp = u128::MAX;
-    let mut uids = vec![];
-    for request in requests {
-        let ts = request
-            .url
-            .split("timestamp=")
-            .nth(1)
-            .and_then(|s| s.split('&').next())
-            .filter(|s| !s.is_empty())
-            .ok_or(zkerr!(ZkErrorCode::CannotFoundTimestamp))?
-            .parse::<u128>()
-            .map_err(|_| zkerr!(ZkErrorCode::ParseTimestampFailed))?;
-        pv.timestamp = pv.timestamp.min(ts);
-
-        // check url
-        if !request.url.starts_with(FEATURE_BALANCE_URL) {
-            return Err(zkerr!(ZkErrorCode::InvalidRequestUrl));
-        }
-
-        {
-            // uid
-            let json_value = messages[i]
-                .get_json_values(&uid_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-            if json_value.len() == 0 {
-                continue; // no any data of future response
-            }
-
-            ensure_zk!(json_value.len() > 0, zkerr!(ZkErrorCode::InvalidJsonValueSize));
-
-            let uid = json_value[0].trim_matches('"').to_string();
-            uids.push(uid);
-        }
-
-        {
-            // balance
-            let json_value = messages[i]
-                .get_json_values(&bal_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(
-                json_value.len() % bal_paths.len() == 0,
-                zkerr!(ZkErrorCode::InvalidJsonValueSize)
-            );
-
-            let size = json_value.len() / bal_paths.len();
-            for j in 0..size {
-                let asset = json_value[j].trim_matches('"').to_ascii_uppercase();
-                let bal: f64 = json_value[size + j].trim_matches('"').parse().unwrap_or(0.0);
-                let un_pnl: f64 = json_value[size * 2 + j].trim_matches('"').parse().unwrap_or(0.0);
-                *asset_bals.entry(asset.to_string()).or_insert(0.0) += bal + un_pnl;
-            }
-        }
-
-        i += 1;
-    }
-
-    // Is the account duplicate?
-    let mut seen = HashSet::new();
-    ensure_zk!(
-        !uids.iter().any(|x| !seen.insert(x)),
-        zkerr!(ZkErrorCode::DuplicateAccount)
-    );
-
-    Ok(())
+/// Field `key` of `__meta__`. Empty if unset.
+fn meta_field(attestations: &HashMap<String, String>, key: &str) -> Result<String, ZktlsError> {
+    let Some(meta) = attestations.get("__meta__") else {
+        return Ok(String::new());
+    };
+    let meta: HashMap<String, String> =
+        serde_json::from_str(meta).map_err(|e| zkerr!(ZkErrorCode::ParseMetaData, e.to_string()))?;
+    Ok(meta.get(key).cloned().unwrap_or_default())
 }
 
-fn app_binance_unified(
-    pv: &mut AttestationMetaStruct,
-    attestation_data: &String,
-    attestation_config: &AttestationConfig,
-    asset_bals: &mut HashMap<String, f64>,
-) -> Result<(), ZktlsError> {
-    //
-    // 1. Verify
-    let mut attestation_config = attestation_config.clone();
-    attestation_config.url = vec![RISK_URL.to_string(), BALANCE_URL.to_string()];
-    let attestation_config = serde_json::to_string(&attestation_config).unwrap();
-    let (attestation_data, _, messages) = verify_attestation_data(&attestation_data, &attestation_config)
-        .map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, e.to_string()))?;
-
-    pv.task_id = attestation_data.public_data[0].taskId.clone();
-    pv.report_tx_hash = attestation_data.public_data[0].reportTxHash.clone();
-    pv.attestor = attestation_data.public_data[0].attestor.clone();
-    pv.base_urls.push(RISK_URL.to_string());
-    pv.base_urls.push(BALANCE_URL.to_string());
-
-    //
-    // 2. Do some valid checks
-    // In the vast majority of cases, it is legal. Data is extracted while the inspection is conducted.
-    let msg_len = messages.len();
-    let requests = attestation_data.public_data[0].attestation.request.clone();
-    let requests_len = requests.len();
-    ensure_zk!(requests_len % 2 == 0, zkerr!(ZkErrorCode::InvalidRequestLength));
-    ensure_zk!(requests_len == msg_len, zkerr!(ZkErrorCode::InvalidMessagesLength));
-
-    let mut i = 0;
-    let mut um_paths = vec![];
-    um_paths.push("$.[*].symbol");
-    um_paths.push("$.[*].entryPrice");
-
-    let mut bal_paths = vec![];
-    bal_paths.push("$.[*].asset");
-    bal_paths.push("$.[*].totalWalletBalance");
-    bal_paths.push("$.[*].umUnrealizedPNL");
-
-    pv.timestamp = u128::MAX;
-    let mut um_prices = vec![];
-    // strict order: um1 bal1 um2 bal2 ...
-    for request in requests {
-        let ts = request
-            .url
-            .split("timestamp=")
-            .nth(1)
-            .and_then(|s| s.split('&').next())
-            .filter(|s| !s.is_empty())
-            .ok_or(zkerr!(ZkErrorCode::CannotFoundTimestamp))?
-            .parse::<u128>()
-            .map_err(|_| zkerr!(ZkErrorCode::ParseTimestampFailed))?;
-        pv.timestamp = pv.timestamp.min(ts);
-
-        // check url and get assets' balance
-        if request.url.starts_with(RISK_URL) {
-            ensure_zk!(i % 2 == 0, zkerr!(ZkErrorCode::InvalidRequestOrder));
-
-            let json_value = messages[i]
-                .get_json_values(&um_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(
-                json_value.len() % um_paths.len() == 0,
-                zkerr!(ZkErrorCode::InvalidJsonValueSize)
-            );
-
-            // Collects UM (asset => entryPrice) info
-            let mut prices = vec![];
-            let size = json_value.len() / um_paths.len();
-            for j in 0..size {
-                let asset = json_value[j].trim_matches('"').to_ascii_uppercase();
-                let price = json_value[size + j].trim_matches('"').to_string();
-                let v = format!("{}:{}", asset, price);
-                prices.push(v);
-            }
-            prices.sort();
-            let um_price = prices.join(",");
-            if !um_price.is_empty() {
-                um_prices.push(um_price);
-            }
-        } else if request.url.starts_with(BALANCE_URL) {
-            let json_value = messages[i]
-                .get_json_values(&bal_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(
-                json_value.len() % bal_paths.len() == 0,
-                zkerr!(ZkErrorCode::InvalidJsonValueSize)
-            );
-
-            let size = json_value.len() / bal_paths.len();
-            for j in 0..size {
-                let asset = json_value[j].trim_matches('"').to_ascii_uppercase();
-                let bal: f64 = json_value[size + j].trim_matches('"').parse().unwrap_or(0.0);
-                let pnl: f64 = json_value[size * 2 + j].trim_matches('"').parse().unwrap_or(0.0);
-                *asset_bals.entry(asset.to_string()).or_insert(0.0) += bal + pnl;
-            }
-        } else {
-            return Err(zkerr!(ZkErrorCode::InvalidRequestUrl));
-        }
-
-        i += 1;
-    }
+/// Min length of a salt of `__meta__`, e.g. the hex of 16 random bytes.
+const MIN_SALT_LEN: usize = 32;
 
-    // Is the account duplicate?
-    let mut seen = HashSet::new();
+/// Salt `key` of `__meta__`. Fails if it is shorter than `MIN_SALT_LEN`, unless it is unset and not `required`.
+fn meta_salt(attestations: &HashMap<String, String>, key: &str, required: bool) -> Result<String, ZktlsError> {
+    let salt = meta_field(attestations, key)?;
     ensure_zk!(
-        !um_prices.iter().any(|x| !seen.insert(x)),
-        zkerr!(ZkErrorCode::DuplicateAccount)
+        salt.len() >= MIN_SALT_LEN || (salt.is_empty() && !required),
+        zkerr!(
+            ZkErrorCode::InvalidSalt,
+            format!("{} of {} chars, {} required", key, salt.len(), MIN_SALT_LEN)
+        )
     );
-
-    Ok(())
+    Ok(salt)
 }
 
-fn app_aster_spot(
-    pv: &mut AttestationMetaStruct,
-    attestation_data: &String,
-    attestation_config: &AttestationConfig,
-    asset_bals: &mut HashMap<String, f64>,
-) -> Result<(), ZktlsError> {
-    //
-    // 1. Verify
-    let mut attestation_config = attestation_config.clone();
-    attestation_config.url = vec![ASTER_SPOT_BALANCE_URL.to_string()];
-    let attestation_config = serde_json::to_string(&attestation_config).unwrap();
-    let (attestation_data, _, messages) = verify_attestation_data(&attestation_data, &attestation_config)
-        .map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, e.to_string()))?;
-
-    pv.task_id = attestation_data.public_data[0].taskId.clone();
-    pv.report_tx_hash = attestation_data.public_data[0].reportTxHash.clone();
-    pv.attestor = attestation_data.public_data[0].attestor.clone();
-    pv.base_urls.push(ASTER_SPOT_BALANCE_URL.to_string());
-    //
-    // 2. Do some valid checks
-    // In the vast majority of cases, it is legal. Data is extracted while the inspection is conducted.
-    let msg_len = messages.len();
-    let requests = attestation_data.public_data[0].attestation.request.clone();
-    let requests_len = requests.len();
-    ensure_zk!(requests_len == msg_len, zkerr!(ZkErrorCode::InvalidMessagesLength));
-
-    let mut i = 0;
-    let mut uid_paths = vec![];
-    uid_paths.push("$.updateTime");
-
-    let mut bal_paths = vec![];
-    bal_paths.push("$.balances[*].asset");
-    bal_paths.push("$.balances[*].free");
-    bal_paths.push("$.balances[*].locked");
-
-    pv.timestamp = u128::MAX;
-    let mut uids = vec![];
-    for request in requests {
-        let ts = request
-            .url
-            .split("timestamp=")
-            .nth(1)
-            .and_then(|s| s.split('&').next())
-            .filter(|s| !s.is_empty())
-            .ok_or(zkerr!(ZkErrorCode::CannotFoundTimestamp))?
-            .parse::<u128>()
-            .map_err(|_| zkerr!(ZkErrorCode::ParseTimestampFailed))?;
-        pv.timestamp = pv.timestamp.min(ts);
-
-        // check url
-        if !request.url.starts_with(ASTER_SPOT_BALANCE_URL) {
-            return Err(zkerr!(ZkErrorCode::InvalidRequestUrl));
-        }
-
-        let update_time;
-        {
-            // uid
-            let json_value = messages[i]
-                .get_json_values(&uid_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(json_value.len() == 1, zkerr!(ZkErrorCode::InvalidJsonValueSize));
-
-            update_time = json_value[0].trim_matches('"').to_string();
-        }
-
-        {
-            // balance
-            let json_value = messages[i]
-                .get_json_values(&bal_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(
-                json_value.len() % bal_paths.len() == 0,
-                zkerr!(ZkErrorCode::InvalidJsonValueSize)
-            );
-
-            let mut _uid = vec![];
-            let size = json_value.len() / bal_paths.len();
-            for j in 0..size {
-                let asset = json_value[j].trim_matches('"').to_ascii_uppercase();
-                let free: f64 = json_value[size + j].trim_matches('"').parse().unwrap_or(0.0);
-                let locked: f64 = json_value[size * 2 + j].trim_matches('"').parse().unwrap_or(0.0);
-                *asset_bals.entry(asset.to_string()).or_insert(0.0) += free + locked;
-
-                // for uid check
-                let v = format!("{}:{}:{}", asset, free, locked);
-                _uid.push(v);
-            }
-            _uid.sort();
-            let _uid = _uid.join(",");
-            if !_uid.is_empty() {
-                let _uid = format!("{}:{}", update_time, _uid);
-                uids.push(_uid);
-            }
-        }
-
-        i += 1;
-    }
-
-    // Is the account duplicate?
-    let mut seen = HashSet::new();
-    ensure_zk!(
-        !uids.iter().any(|x| !seen.insert(x)),
-        zkerr!(ZkErrorCode::DuplicateAccount)
-    );
-
-    Ok(())
-}
-
-fn app_aster_future(
-    pv: &mut AttestationMetaStruct,
-    attestation_data: &String,
-    attestation_config: &AttestationConfig,
-    asset_bals: &mut HashMap<String, f64>,
+fn app_main(
+    pv: &mut PublicValuesStruct,
+    config_data: &str,
+    attestations: &HashMap<String, String>,
+    program_config: &ProgramConfig,
 ) -> Result<(), ZktlsError> {
-    //
-    // 1. Verify
-    let mut attestation_config = attestation_config.clone();
-    attestation_config.url = vec![ASTER_FEATURE_BALANCE_URL.to_string()];
-    let attestation_config = serde_json::to_string(&attestation_config).unwrap();
-    let (attestation_data, _, messages) = verify_attestation_data(&attestation_data, &attestation_config)
-        .map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, e.to_string()))?;
-
-    pv.task_id = attestation_data.public_data[0].taskId.clone();
-    pv.report_tx_hash = attestation_data.public_data[0].reportTxHash.clone();
-    pv.attestor = attestation_data.public_data[0].attestor.clone();
-    pv.base_urls.push(ASTER_FEATURE_BALANCE_URL.to_string());
-
-    //
-    // 2. Do some valid checks
-    // In the vast majority of cases, it is legal. Data is extracted while the inspection is conducted.
-    let msg_len = messages.len();
-    let requests = attestation_data.public_data[0].attestation.request.clone();
-    let requests_len = requests.len();
-    ensure_zk!(requests_len == msg_len, zkerr!(ZkErrorCode::InvalidMessagesLength));
-
-    let mut i = 0;
-    let mut uid_paths = vec![];
-    uid_paths.push("$.[*].accountAlias");
-
-    let mut bal_paths = vec![];
-    bal_paths.push("$.[*].asset");
-    bal_paths.push("$.[*].balance");
-    bal_paths.push("$.[*].crossUnPnl");
-
-    pv.timestamp = u128::MAX;
-    let mut uids = vec![];
-    for request in requests {
-        let ts = request
-            .url
-            .split("timestamp=")
-            .nth(1)
-            .and_then(|s| s.split('&').next())
-            .filter(|s| !s.is_empty())
-            .ok_or(zkerr!(ZkErrorCode::CannotFoundTimestamp))?
-            .parse::<u128>()
-            .map_err(|_| zkerr!(ZkErrorCode::ParseTimestampFailed))?;
-        pv.timestamp = pv.timestamp.min(ts);
-
-        // check url
-        if !request.url.starts_with(ASTER_FEATURE_BALANCE_URL) {
-            return Err(zkerr!(ZkErrorCode::InvalidRequestUrl));
-        }
-
-        {
-            // uid
-            let json_value = messages[i]
-                .get_json_values(&uid_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-            if json_value.len() == 0 {
-                continue; // no any data of future response
-            }
-
-            ensure_zk!(json_value.len() > 0, zkerr!(ZkErrorCode::InvalidJsonValueSize));
-
-            let uid = json_value[0].trim_matches('"').to_string();
-            uids.push(uid);
-        }
-
-        {
-            // balance
-            let json_value = messages[i]
-                .get_json_values(&bal_paths)
-                .map_err(|e| zkerr!(ZkErrorCode::GetJsonValueFail, e.to_string()))?;
-
-            ensure_zk!(
-                json_value.len() % bal_paths.len() == 0,
-                zkerr!(ZkErrorCode::InvalidJsonValueSize)
-            );
+    set_meta(&mut pv.project_id, attestations)?;
+    pv.attestor_allowlist_hash = program_config.attestor_allowlist_hash();
+    pv.min_attestors = program_config.required_attestors() as u64;
+    pv.freshness = program_config.freshness.clone();
+    pv.onchain_hash = program_config.onchain_hash();
+    pv.classification_hash = program_config.classification_hash();
 
-            let size = json_value.len() / bal_paths.len();
-            for j in 0..size {
-                let asset = json_value[j].trim_matches('"').to_ascii_uppercase();
-                let bal: f64 = json_value[size + j].trim_matches('"').parse().unwrap_or(0.0);
-                let un_pnl: f64 = json_value[size * 2 + j].trim_matches('"').parse().unwrap_or(0.0);
-                *asset_bals.entry(asset.to_string()).or_insert(0.0) += bal + un_pnl;
-            }
-        }
-
-        i += 1;
-    }
-
-    // Is the account duplicate?
-    let mut seen = HashSet::new();
-    ensure_zk!(
-        !uids.iter().any(|x| !seen.insert(x)),
-        zkerr!(ZkErrorCode::DuplicateAccount)
-    );
-
-    Ok(())
+    let attestation_config: AttestationConfig =
+        serde_json::from_str(config_data).map_err(|e| zkerr!(ZkErrorCode::ParseConfigData, e.to_string()))?;
+
+    // Nothing verified is committed before the disclosure scope is applied
+    let mut detail = Detail::default();
+    let mut accounts = vec![];
+    for venue in sources::VENUES {
+        let asset_bals = sources::verify_venue(
+            &mut pv.attestation_meta,
+            &mut accounts,
+            venue,
+            attestations,
+            &attestation_config,
+            program_config,
+        )?;
+        detail.asset_balance.insert(venue.to_string(), asset_bals);
+    }
+    detail.asset_balance.extend(sources::verify_chains(
+        &mut pv.attestation_meta,
+        &mut accounts,
+        &mut pv.block_numbers,
+        attestations,
+        &attestation_config,
+        program_config,
+    )?);
+
+    // Value the reserves in USD
+    if let Some(valuation) = &program_config.valuation {
+        let prices = valuation::verify_prices(
+            &mut pv.attestation_meta,
+            attestations,
+            &attestation_config,
+            program_config,
+        )?;
+        let reserves = detail.reserves()?;
+        detail.usd_price = valuation::usd_prices(&reserves, &prices, valuation)?;
+        detail.usd_value = valuation::usd_values(&reserves, &detail.usd_price)?;
+    }
+
+    sources::check_freshness(&pv.attestation_meta, &program_config.freshness)?;
+
+    // The host rebuilds the accounts from the same responses to open them, see `por-inclusion accounts`
+    // Salted, so the root hides the accounts whatever the disclosure scope; without a salt no root is committed
+    let account_salt = meta_salt(attestations, "accountSalt", false)?;
+    if !account_salt.is_empty() {
+        let account_tree = AccountTree::build(accounts, account_salt.as_bytes());
+        pv.account_root = account_tree.root_hex();
+        pv.account_count = account_tree.account_count();
+    }
+
+    // The default scope discloses the detail anyway, the others hide it behind `detail_hash`
+    let hidden = program_config.disclosure != DisclosureScope::PerAssetPerSource;
+    let detail_salt = meta_salt(attestations, "detailSalt", hidden)?;
+    disclosure::app_disclosure(pv, &detail, &detail_salt, program_config)
 }
 
-fn app_binance(
-    pv: &mut PublicValuesStruct,
+fn app_solvency_main(
+    pv: &mut SolvencyPublicValuesStruct,
+    config_data: &str,
     attestations: &HashMap<String, String>,
-    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
 ) -> Result<(), ZktlsError> {
-    // Verify Unified, Spot and Future
-    let mut asset_bals: HashMap<String, f64> = HashMap::new();
-
-    if let Some(spot_data) = attestations.get("binanceSpot") {
-        let mut spot_am = AttestationMetaStruct::default();
-        app_binance_spot(&mut spot_am, &spot_data, attestation_config, &mut asset_bals)?;
-        pv.attestation_meta.push(spot_am);
-    }
-
-    if let Some(future_data) = attestations.get("binanceUsdSFuture") {
-        let mut future_am = AttestationMetaStruct::default();
-        app_binance_future(&mut future_am, &future_data, attestation_config, &mut asset_bals)?;
-        pv.attestation_meta.push(future_am);
-    }
-
-    if let Some(unified_data) = attestations.get("binanceUnified") {
-        let mut unified_am = AttestationMetaStruct::default();
-        app_binance_unified(&mut unified_am, &unified_data, attestation_config, &mut asset_bals)?;
-        pv.attestation_meta.push(unified_am);
-    }
+    set_meta(&mut pv.project_id, attestations)?;
+    pv.attestor_allowlist_hash = program_config.attestor_allowlist_hash();
+    pv.min_attestors = program_config.required_attestors() as u64;
+    pv.freshness = program_config.freshness.clone();
+    pv.onchain_hash = program_config.onchain_hash();
 
-    // Summary assets by Category
-    let mut asset_balance: HashMap<String, f64> = HashMap::new();
-    let mut stablecoin_sum = 0.0;
-    for (k, v) in asset_bals {
-        if STABLE_COINS.contains(&k.as_str()) {
-            stablecoin_sum += v;
-        } else {
-            if v > EPSILON_VALUE {
-                asset_balance.insert(k, v);
-            }
-        }
-    }
-    if stablecoin_sum > EPSILON_VALUE {
-        asset_balance.insert("STABLECOIN".to_string(), stablecoin_sum);
-    }
-    pv.asset_balance.insert("binance".to_string(), asset_balance);
+    let attestation_config: AttestationConfig =
+        serde_json::from_str(config_data).map_err(|e| zkerr!(ZkErrorCode::ParseConfigData, e.to_string()))?;
 
-    Ok(())
+    solvency::app_solvency(pv, attestations, &attestation_config, program_config)
 }
 
-fn app_aster(
-    pv: &mut PublicValuesStruct,
+fn app_liability_main(
+    pv: &mut LiabilityPublicValuesStruct,
     attestations: &HashMap<String, String>,
-    attestation_config: &AttestationConfig,
 ) -> Result<(), ZktlsError> {
-    // Verify Spot and Future
-    let mut asset_bals: HashMap<String, f64> = HashMap::new();
-
-    if let Some(spot_data) = attestations.get("asterSpot") {
-        let mut spot_am = AttestationMetaStruct::default();
-        app_aster_spot(&mut spot_am, &spot_data, attestation_config, &mut asset_bals)?;
-        pv.attestation_meta.push(spot_am);
-    }
-    if let Some(future_data) = attestations.get("asterUsdSFuture") {
-        let mut future_am = AttestationMetaStruct::default();
-        app_aster_future(&mut future_am, &future_data, attestation_config, &mut asset_bals)?;
-        pv.attestation_meta.push(future_am);
-    }
-
-    // Summary assets by Category
-    let mut asset_balance: HashMap<String, f64> = HashMap::new();
-    let mut stablecoin_sum = 0.0;
-    for (k, v) in asset_bals {
-        if STABLE_COINS.contains(&k.as_str()) {
-            stablecoin_sum += v;
-        } else {
-            if v > EPSILON_VALUE {
-                asset_balance.insert(k, v);
-            }
-        }
-    }
-    if stablecoin_sum > EPSILON_VALUE {
-        asset_balance.insert("STABLECOIN".to_string(), stablecoin_sum);
-    }
-    pv.asset_balance.insert("aster".to_string(), asset_balance);
+    set_meta(&mut pv.project_id, attestations)?;
 
-    Ok(())
+    liability::app_liability(pv, attestations)
 }
 
-/// Helper function
-fn set_meta(pv: &mut PublicValuesStruct, attestations: &HashMap<String, String>) -> Result<(), ZktlsError> {
-    if let Some(meta) = attestations.get("__meta__") {
-        let meta: HashMap<String, String> =
-            serde_json::from_str(meta).map_err(|e| zkerr!(ZkErrorCode::ParseMetaData, e.to_string()))?;
-
-        pv.project_id = meta
-            .get("projectId")
-            .ok_or_else(|| zkerr!(ZkErrorCode::MissingProjectId))?
-            .to_owned();
+/// Records the result in `pv` and commits it.
+fn finish<T: PublicValues>(pv: &mut T, result: Result<(), ZktlsError>, output_mode: OutputMode) {
+    // A value the ABI cannot carry fails the run
+    let result = result.and_then(|()| match output_mode {
+        OutputMode::Abi => pv.abi_encode().map(drop),
+        OutputMode::Serde => Ok(()),
+    });
+    let mut status = 0;
+    if let Err(e) = result {
+        println!("Error: {} {}", e.icode(), e.msg());
+        status = e.icode();
+        pv.set_status(status);
+    } else {
+        println!("OK");
+    }
+    let digest = pv.digest();
+    pv.set_digest(digest);
+    match output_mode {
+        OutputMode::Serde => commit(pv),
+        // A failed run whose values cannot be encoded commits its status only, default values always encode
+        OutputMode::Abi => commit_slice(&pv.abi_encode().unwrap_or_else(|_| {
+            let mut failed = T::default();
+            failed.set_status(status);
+            failed.abi_encode().unwrap_or_default()
+        })),
     }
-
-    Ok(())
 }
 
-fn app_main(pv: &mut PublicValuesStruct) -> Result<(), ZktlsError> {
+pub fn main() {
     let config_data: String = sp1_zkvm::io::read();
     let attestations: HashMap<String, String> = sp1_zkvm::io::read();
 
-    set_meta(pv, &attestations)?;
-
-    let attestation_config: AttestationConfig =
-        serde_json::from_str(&config_data).map_err(|e| zkerr!(ZkErrorCode::ParseConfigData, e.to_string()))?;
-
-    app_binance(pv, &attestations, &attestation_config)?;
-    app_aster(pv, &attestations, &attestation_config)?;
-
-    Ok(())
-}
-
-pub fn main() {
-    let mut pv = PublicValuesStruct::default();
-    pv.version = "0.1.0".to_string();
-    pv.kind = "asset-balance".to_string();
-    if let Err(e) = app_main(&mut pv) {
-        println!("Error: {} {}", e.icode(), e.msg());
-        pv.status = e.icode();
-    } else {
-        println!("OK");
+    // The kind and output mode are needed to report even an invalid config.
+    let program_config: Result<ProgramConfig, ZktlsError> =
+        serde_json::from_str(&config_data).map_err(|e| zkerr!(ZkErrorCode::ParseConfigData, e.to_string()));
+    let (kind, output_mode) = match &program_config {
+        Ok(c) => (c.kind, c.output_mode),
+        Err(_) => Default::default(),
+    };
+
+    match kind {
+        ProofKind::AssetBalance => {
+            let mut pv = PublicValuesStruct {
+                version: PUBLIC_VALUES_VERSION.to_string(),
+                kind: "asset-balance".to_string(),
+                ..Default::default()
+            };
+            let result = program_config.and_then(|c| app_main(&mut pv, &config_data, &attestations, &c));
+            finish(&mut pv, result, output_mode);
+        }
+        ProofKind::Liability => {
+            let mut pv = LiabilityPublicValuesStruct {
+                version: PUBLIC_VALUES_VERSION.to_string(),
+                kind: "liability".to_string(),
+                ..Default::default()
+            };
+            let result = program_config.and_then(|_| app_liability_main(&mut pv, &attestations));
+            finish(&mut pv, result, output_mode);
+        }
+        ProofKind::Solvency => {
+            let mut pv = SolvencyPublicValuesStruct {
+                version: PUBLIC_VALUES_VERSION.to_string(),
+                kind: "solvency".to_string(),
+                ..Default::default()
+            };
+            let result = program_config.and_then(|c| app_solvency_main(&mut pv, &config_data, &attestations, &c));
+            finish(&mut pv, result, output_mode);
+        }
     }
-    commit(&pv);
 }
@@ -0,0 +1,68 @@
+use por_lib::decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use zktls_att_verification::attestation_data::AttestationConfig;
+
+use crate::errors::ZktlsError;
+use crate::liability::build_liability_tree;
+use crate::sources::{add_balance, check_freshness, verify_chains, verify_venue, VENUES};
+use crate::structs::{ProgramConfig, SolvencyPublicValuesStruct, SolvencyStruct};
+
+/// Fractional digits of the committed reserve ratios.
+const RATIO_SCALE: u32 = 4;
+
+/// Checks, per liability asset, the reserves verified on every venue and chain against the customer liabilities.
+pub fn app_solvency(
+    pv: &mut SolvencyPublicValuesStruct,
+    attestations: &HashMap<String, String>,
+    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
+) -> Result<(), ZktlsError> {
+    let mut reserves: BTreeMap<String, Decimal> = BTreeMap::new();
+    // Not committed by the solvency kind
+    let mut accounts = vec![];
+    for venue in VENUES {
+        let asset_bals = verify_venue(
+            &mut pv.attestation_meta,
+            &mut accounts,
+            venue,
+            attestations,
+            attestation_config,
+            program_config,
+        )?;
+        for (asset, bal) in &asset_bals {
+            add_balance(&mut reserves, asset, bal)?;
+        }
+    }
+    let chains = verify_chains(
+        &mut pv.attestation_meta,
+        &mut accounts,
+        &mut pv.block_numbers,
+        attestations,
+        attestation_config,
+        program_config,
+    )?;
+    for (asset, bal) in chains.values().flatten() {
+        add_balance(&mut reserves, asset, bal)?;
+    }
+    check_freshness(&pv.attestation_meta, &program_config.freshness)?;
+
+    let tree = build_liability_tree(attestations)?;
+    pv.liability_root = tree.root_hex();
+    pv.user_count = tree.user_count();
+
+    for (asset, liability) in &tree.totals() {
+        let reserve = reserves.get(asset).copied().unwrap_or_default();
+        let solvency = SolvencyStruct {
+            reserve_ratio: reserve.checked_div(liability, RATIO_SCALE),
+            solvent: reserve >= *liability,
+        };
+        pv.solvency.insert(asset.clone(), solvency);
+
+        if program_config.reveal_amounts {
+            pv.reserve_balance.insert(asset.clone(), reserve);
+            pv.liability_balance.insert(asset.clone(), *liability);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use por_lib::account::AccountEntry;
+use por_lib::attestation::{self, AttestationError, AttestedEntry, FreshnessConfig};
+use por_lib::decimal::Decimal;
+use por_lib::sources::{extract, AttestedRequest, DataSource, Response, SourceError};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use zktls_att_verification::attestation_data::{verify_attestation_data, AttestationConfig, AttestationData, Message};
+
+use crate::errors::{ZkErrorCode, ZktlsError};
+use crate::structs::{AttestationMetaStruct, ProgramConfig};
+use crate::{ensure_zk, zkerr};
+
+mod onchain;
+
+pub use onchain::verify_chains;
+pub use por_lib::sources::{add_balance, PRICE_SOURCES, REGISTRY, VENUES};
+
+impl From<SourceError> for ZktlsError {
+    fn from(e: SourceError) -> Self {
+        let code = match e {
+            SourceError::InvalidRequestLength => ZkErrorCode::InvalidRequestLength,
+            SourceError::InvalidRequestOrder => ZkErrorCode::InvalidRequestOrder,
+            SourceError::InvalidRequestUrl => ZkErrorCode::InvalidRequestUrl,
+            SourceError::InvalidRequestBody(_) => ZkErrorCode::InvalidRequestBody,
+            SourceError::InvalidRpcRequest(_) => ZkErrorCode::InvalidRpcRequest,
+            SourceError::BlockNumberMismatch(_) => ZkErrorCode::BlockNumberMismatch,
+            SourceError::CannotFoundTimestamp => ZkErrorCode::CannotFoundTimestamp,
+            SourceError::ParseTimestampFailed => ZkErrorCode::ParseTimestampFailed,
+            SourceError::GetJsonValueFail(_) => ZkErrorCode::GetJsonValueFail,
+            SourceError::InvalidJsonValueSize => ZkErrorCode::InvalidJsonValueSize,
+            SourceError::ParseBalanceFailed(_) => ZkErrorCode::ParseBalanceFailed,
+            SourceError::BalanceOverflow(_) => ZkErrorCode::BalanceOverflow,
+            SourceError::MissingAccountId(_) => ZkErrorCode::MissingAccountId,
+            SourceError::DuplicateAccount(_) => ZkErrorCode::DuplicateAccount,
+        };
+        ZktlsError::new(code, e.to_string())
+    }
+}
+
+impl From<AttestationError> for ZktlsError {
+    fn from(e: AttestationError) -> Self {
+        let code = match e {
+            AttestationError::TimestampInFuture(_) => ZkErrorCode::TimestampInFuture,
+            AttestationError::AttestationTooOld(_) => ZkErrorCode::AttestationTooOld,
+            AttestationError::TimestampSpreadTooLarge(_) => ZkErrorCode::TimestampSpreadTooLarge,
+            AttestationError::UnknownAttestor(_) => ZkErrorCode::UnknownAttestor,
+            AttestationError::InsufficientAttestors(_) => ZkErrorCode::InsufficientAttestors,
+        };
+        ZktlsError::new(code, e.to_string())
+    }
+}
+
+/// A verified response, read by the JSONPaths of the attestation crate.
+struct Attested<'a>(&'a Message);
+
+impl Response for Attested<'_> {
+    fn json_values(&self, paths: &[&str]) -> Result<Vec<String>, String> {
+        self.0.get_json_values(&paths.to_vec()).map_err(|e| e.to_string())
+    }
+}
+
+/// The requests of the first entry of `attestation_data`, each with its verified response.
+fn attested_requests<'a>(
+    attestation_data: &'a AttestationData,
+    messages: &'a [Message],
+) -> Result<Vec<AttestedRequest<'a, Attested<'a>>>, ZktlsError> {
+    let requests = &attestation_data.public_data[0].attestation.request;
+    ensure_zk!(
+        requests.len() == messages.len(),
+        zkerr!(ZkErrorCode::InvalidMessagesLength)
+    );
+    Ok(requests
+        .iter()
+        .zip(messages)
+        .map(|(request, message)| AttestedRequest {
+            url: &request.url,
+            body: &request.body,
+            response: Attested(message),
+        })
+        .collect())
+}
+
+/// Records the attestors of `entries` in `pv` and checks them, see `por_lib::attestation::check_attestors`.
+fn check_attestors(
+    pv: &mut AttestationMetaStruct,
+    entries: &[AttestedEntry],
+    program_config: &ProgramConfig,
+) -> Result<(), ZktlsError> {
+    if let Some(first) = entries.first() {
+        pv.task_id = first.task_id.to_string();
+        pv.report_tx_hash = first.report_tx_hash.to_string();
+        pv.attestor = first.attestor.to_string();
+    }
+    for entry in entries {
+        pv.attestors.push(entry.attestor.to_string());
+        pv.task_ids.push(entry.task_id.to_string());
+    }
+    Ok(attestation::check_attestors(
+        entries,
+        &program_config.attestor_allowlist,
+        program_config.required_attestors(),
+    )?)
+}
+
+/// Verifies the attestation `attestation_data`, its requests limited to `urls`, and checks its attestors.
+/// Returns the verified attestation data and the messages of its first entry.
+fn verify_attested(
+    pv: &mut AttestationMetaStruct,
+    attestation_data: &str,
+    urls: Vec<String>,
+    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
+) -> Result<(AttestationData, Vec<Message>), ZktlsError> {
+    let mut attestation_config = attestation_config.clone();
+    attestation_config.url = urls.clone();
+    let attestation_config = serde_json::to_string(&attestation_config).unwrap();
+    let (verified, _, messages) = verify_attestation_data(attestation_data, &attestation_config)
+        .map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, e.to_string()))?;
+
+    let entries = verified
+        .public_data
+        .iter()
+        .map(|pd| AttestedEntry {
+            task_id: &pd.taskId,
+            report_tx_hash: &pd.reportTxHash,
+            attestor: &pd.attestor,
+            requests: pd
+                .attestation
+                .request
+                .iter()
+                .map(|r| (r.url.as_str(), r.body.as_str()))
+                .collect(),
+            data: &pd.attestation.data,
+        })
+        .collect::<Vec<_>>();
+    check_attestors(pv, &entries, program_config)?;
+    verify_entries(attestation_data, &attestation_config)?;
+    pv.base_urls.extend(urls);
+
+    Ok((verified, messages))
+}
+
+/// Verifies every further `public_data` entry on its own, as the attestation crate is relied on
+/// for the signature of the first entry only.
+fn verify_entries(attestation_data: &str, attestation_config: &str) -> Result<(), ZktlsError> {
+    let data: Value =
+        serde_json::from_str(attestation_data).map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, e.to_string()))?;
+    let entries = data["public_data"].as_array().cloned().unwrap_or_default();
+    for (i, entry) in entries.into_iter().enumerate().skip(1) {
+        let mut single = data.clone();
+        single["public_data"] = Value::Array(vec![entry]);
+        verify_attestation_data(&single.to_string(), attestation_config)
+            .map_err(|e| zkerr!(ZkErrorCode::VerifyAttestation, format!("entry {}: {}", i, e)))?;
+    }
+    Ok(())
+}
+
+/// Verifies the attestation of `source` and reads its balances into `asset_bals` and, if given,
+/// its accounts into `accounts`, see `por_lib::sources::extract`.
+pub fn verify_source(
+    source: &dyn DataSource,
+    pv: &mut AttestationMetaStruct,
+    attestation_data: &str,
+    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
+    asset_bals: &mut BTreeMap<String, Decimal>,
+    accounts: Option<&mut Vec<AccountEntry>>, // None for sources of no accounts, e.g. prices
+) -> Result<(), ZktlsError> {
+    let (attestation_data, messages) = verify_attested(
+        pv,
+        attestation_data,
+        source.urls().iter().map(|u| u.to_string()).collect(),
+        attestation_config,
+        program_config,
+    )?;
+    let requests = attested_requests(&attestation_data, &messages)?;
+    let attested_at = attestation_data.public_data[0].attestation.timestamp as u128;
+    (pv.timestamp, pv.timestamp_max) = extract(
+        source,
+        &requests,
+        attested_at,
+        program_config.balance_parse_mode,
+        asset_bals,
+        accounts,
+    )?;
+
+    Ok(())
+}
+
+/// Verifies every attestation of `venue`, returning its balances per asset.
+pub fn verify_venue(
+    attestation_meta: &mut Vec<AttestationMetaStruct>,
+    accounts: &mut Vec<AccountEntry>,
+    venue: &str,
+    attestations: &HashMap<String, String>,
+    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
+) -> Result<BTreeMap<String, Decimal>, ZktlsError> {
+    let first = accounts.len();
+    let mut receipts = HashSet::new(); // (account id, receipt token)
+    for source in REGISTRY.iter().filter(|s| s.venue() == venue) {
+        if let Some(data) = attestations.get(source.key()) {
+            let mut am = AttestationMetaStruct::default();
+            let from = accounts.len();
+            verify_source(
+                *source,
+                &mut am,
+                data,
+                attestation_config,
+                program_config,
+                &mut BTreeMap::new(),
+                Some(&mut *accounts),
+            )?;
+            attestation_meta.push(am);
+            if let Some(prefix) = source.receipt_prefix() {
+                for account in &accounts[from..] {
+                    receipts.extend(
+                        account
+                            .balances
+                            .keys()
+                            .map(|asset| (account.account_id.clone(), format!("{}{}", prefix, asset))),
+                    );
+                }
+            }
+        }
+    }
+
+    // A position counts once, under its asset rather than its receipt token, in the accounts
+    // whose positions are verified. The accounts keep the receipts they list.
+    let mut asset_bals: BTreeMap<String, Decimal> = BTreeMap::new();
+    for account in &accounts[first..] {
+        for (asset, bal) in &account.balances {
+            if !receipts.contains(&(account.account_id.clone(), asset.clone())) {
+                add_balance(&mut asset_bals, asset, bal)?;
+            }
+        }
+    }
+
+    Ok(asset_bals)
+}
+
+/// Checks the requests of all attestations against the freshness bounds, if any.
+pub fn check_freshness(
+    attestation_meta: &[AttestationMetaStruct],
+    freshness: &Option<FreshnessConfig>,
+) -> Result<(), ZktlsError> {
+    let times: Vec<(u128, u128)> = attestation_meta
+        .iter()
+        .map(|am| (am.timestamp, am.timestamp_max))
+        .collect();
+    Ok(attestation::check_freshness(&times, freshness)?)
+}
@@ -0,0 +1,82 @@
+use por_lib::account::AccountEntry;
+use por_lib::decimal::Decimal;
+use por_lib::sources::onchain::{extract_chain, OnchainConfig};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use zktls_att_verification::attestation_data::AttestationConfig;
+
+use super::{attested_requests, verify_attested};
+use crate::errors::{ZkErrorCode, ZktlsError};
+use crate::structs::{AttestationMetaStruct, ProgramConfig};
+use crate::{ensure_zk, zkerr};
+
+/// Verifies the attestation of every configured chain, returning their balances per asset by `onchain:<chain>`
+/// and recording the block of each chain attested in `block_numbers`.
+pub fn verify_chains(
+    attestation_meta: &mut Vec<AttestationMetaStruct>,
+    accounts: &mut Vec<AccountEntry>,
+    block_numbers: &mut BTreeMap<String, u64>,
+    attestations: &HashMap<String, String>,
+    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
+) -> Result<BTreeMap<String, BTreeMap<String, Decimal>>, ZktlsError> {
+    // A chain configured twice would count its attestation twice
+    let mut names = HashSet::new();
+    for chain in &program_config.onchain {
+        ensure_zk!(
+            names.insert(chain.chain.as_str()),
+            zkerr!(ZkErrorCode::DuplicateChain, chain.chain.clone())
+        );
+    }
+
+    let mut chains = BTreeMap::new();
+    for chain in &program_config.onchain {
+        let Some(asset_bals) = verify_onchain(
+            attestation_meta,
+            accounts,
+            chain,
+            attestations,
+            attestation_config,
+            program_config,
+        )?
+        else {
+            continue;
+        };
+        chains.insert(chain.key(), asset_bals);
+        block_numbers.insert(chain.chain.clone(), chain.block_number);
+    }
+
+    Ok(chains)
+}
+
+/// Verifies the `eth_getBalance` / `eth_call balanceOf` attestation of `chain`, if any,
+/// returning its balances per asset. Each address is an account.
+fn verify_onchain(
+    attestation_meta: &mut Vec<AttestationMetaStruct>,
+    accounts: &mut Vec<AccountEntry>,
+    chain: &OnchainConfig,
+    attestations: &HashMap<String, String>,
+    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
+) -> Result<Option<BTreeMap<String, Decimal>>, ZktlsError> {
+    let Some(attestation_data) = attestations.get(&chain.key()) else {
+        return Ok(None);
+    };
+    let mut pv = AttestationMetaStruct::default();
+
+    let (attestation_data, messages) = verify_attested(
+        &mut pv,
+        attestation_data,
+        chain.rpc_urls.clone(),
+        attestation_config,
+        program_config,
+    )?;
+    let requests = attested_requests(&attestation_data, &messages)?;
+    let asset_bals = extract_chain(chain, &requests, accounts)?;
+
+    // the state is pinned by the block, the time is the time of the attestation
+    pv.timestamp = attestation_data.public_data[0].attestation.timestamp as u128;
+    pv.timestamp_max = pv.timestamp;
+
+    attestation_meta.push(pv);
+    Ok(Some(asset_bals))
+}
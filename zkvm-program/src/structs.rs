@@ -1,13 +1,241 @@
+use por_lib::attestation::FreshnessConfig;
+use por_lib::decimal::Decimal;
+use por_lib::sources::onchain::OnchainConfig;
+use por_lib::sources::BalanceParseMode;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::errors::ZktlsError;
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct AttestationMetaStruct {
     pub task_id: String,
     pub report_tx_hash: String,
     pub attestor: String,
+    pub attestors: Vec<String>, // every entry of the attestation, in order
+    pub task_ids: Vec<String>,
     pub base_urls: Vec<String>,
-    pub timestamp: u128,
+    pub timestamp: u128,     // earliest request
+    pub timestamp_max: u128, // latest request
+}
+
+/// What the program proves, selects the public values variant.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProofKind {
+    /// Verified exchange balances, `PublicValuesStruct`.
+    #[default]
+    AssetBalance,
+    /// Merkle sum tree of the customer liabilities, `LiabilityPublicValuesStruct`.
+    Liability,
+    /// Verified exchange balances against the customer liabilities, `SolvencyPublicValuesStruct`.
+    Solvency,
+}
+
+/// How the public values are committed.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// The serde-serialized `PublicValuesStruct`.
+    #[default]
+    Serde,
+    /// The Solidity ABI encoding of `abi::PublicValuesAbi`, for on-chain verifier contracts.
+    Abi,
+}
+
+/// What the asset-balance public values disclose; the rest is committed by `detail_hash` only.
+/// `detail_hash` is salted with `detailSalt` of `__meta__`, of at least 32 chars; the scopes other than
+/// the default hide detail, so they fail without it.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisclosureScope {
+    /// Per source, the assets grouped by the classification.
+    #[default]
+    PerAssetPerSource,
+    /// Across sources, the categories of the classification; the other assets as `OTHER`.
+    PerCategory,
+    /// Across sources, the assets grouped by the classification.
+    TotalPerAsset,
+    /// Only `usd_total`, requires `valuation`.
+    UsdTotal,
+    /// Only whether the reserves meet `thresholds`.
+    ThresholdOnly,
+}
+
+impl DisclosureScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisclosureScope::PerAssetPerSource => "per-asset-per-source",
+            DisclosureScope::PerCategory => "per-category",
+            DisclosureScope::TotalPerAsset => "total-per-asset",
+            DisclosureScope::UsdTotal => "usd-total",
+            DisclosureScope::ThresholdOnly => "threshold-only",
+        }
+    }
+}
+
+/// Category of the default classification.
+pub const STABLE_COINS: &[&str] = &[
+    "USDT", "USDC", "FDUSD", "TUSD", "USDE", "XUSD", "USD1", "BFUSD", "USDP", "DAI", "USDF",
+];
+
+fn default_quote() -> String {
+    "USDT".to_string()
+}
+
+fn default_min_price_venues() -> usize {
+    2
+}
+
+/// USD valuation of the reserves from the attested ticker prices of several venues.
+/// The prices are attestations like the balances, so `freshness` bounds them too; it must set
+/// `max_age` or `max_spread`, so the prices are taken in the time window of the balances.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuationConfig {
+    /// Quote asset of the symbols taken as USD prices, e.g. `USDT` for `BTCUSDT`.
+    #[serde(default = "default_quote")]
+    pub quote: String,
+    /// Assets valued at 1 USD. Defaults to the quote asset.
+    #[serde(default)]
+    pub pegged: Vec<String>,
+    /// Venues that must quote an asset for it to be valued, at the median of their prices.
+    #[serde(default = "default_min_price_venues")]
+    pub min_price_venues: usize,
+    /// Max relative deviation of a venue price from the median, e.g. `0.01`.
+    pub max_price_deviation: Decimal,
+}
+
+/// Reserves to prove, `reserves >= threshold`, without disclosing them.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdConfig {
+    /// Asset or category of the classification, case-insensitive => min balance over all sources.
+    #[serde(default)]
+    pub assets: BTreeMap<String, Decimal>,
+    /// Min `usd_total`, requires `valuation`.
+    #[serde(default)]
+    pub usd: Option<Decimal>,
+}
+
+/// Program options, read from the same config input as the `AttestationConfig`.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramConfig {
+    #[serde(default)]
+    pub kind: ProofKind,
+    #[serde(default)]
+    pub balance_parse_mode: BalanceParseMode,
+    #[serde(default)]
+    pub output_mode: OutputMode,
+    /// `solvency` kind: also commit the absolute reserve and liability amounts.
+    #[serde(default)]
+    pub reveal_amounts: bool,
+    #[serde(default)]
+    pub freshness: Option<FreshnessConfig>,
+    /// Addresses of the trusted Primus attestors. Empty trusts any attestor.
+    #[serde(default)]
+    pub attestor_allowlist: Vec<String>,
+    /// Distinct attestors that must agree on each attestation, at least 1. More than 1 requires `attestor_allowlist`.
+    #[serde(default)]
+    pub min_attestors: usize,
+    /// EVM chains holding reserves.
+    #[serde(default)]
+    pub onchain: Vec<OnchainConfig>,
+    /// Category => assets, the assets of a category are reported as one balance under the category,
+    /// together with an unclassified asset of the category's name. Defaults to `STABLECOIN` => `STABLE_COINS`.
+    #[serde(default)]
+    pub classification: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(default)]
+    pub valuation: Option<ValuationConfig>,
+    #[serde(default)]
+    pub disclosure: DisclosureScope,
+    #[serde(default)]
+    pub thresholds: Option<ThresholdConfig>,
+}
+
+impl ProgramConfig {
+    /// Distinct attestors that must agree on each attestation, the m of m-of-n.
+    pub fn required_attestors(&self) -> usize {
+        self.min_attestors.max(1)
+    }
+
+    /// Hex SHA-256 of the sorted, lowercase allowlist as a JSON array. Empty without an allowlist.
+    pub fn attestor_allowlist_hash(&self) -> String {
+        if self.attestor_allowlist.is_empty() {
+            return String::new();
+        }
+        let mut allowlist: Vec<String> = self.attestor_allowlist.iter().map(|a| a.to_ascii_lowercase()).collect();
+        allowlist.sort();
+        allowlist.dedup();
+        hex::encode(Sha256::digest(serde_json::to_vec(&allowlist).unwrap()))
+    }
+
+    /// The classification in use, categories and assets uppercase.
+    pub fn classification(&self) -> BTreeMap<String, BTreeSet<String>> {
+        match &self.classification {
+            Some(classification) => {
+                let mut categories: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+                for (category, assets) in classification {
+                    categories
+                        .entry(category.to_ascii_uppercase())
+                        .or_default()
+                        .extend(assets.iter().map(|a| a.to_ascii_uppercase()));
+                }
+                categories
+            }
+            None => BTreeMap::from([(
+                "STABLECOIN".to_string(),
+                STABLE_COINS.iter().map(|a| a.to_string()).collect(),
+            )]),
+        }
+    }
+
+    /// Asset => category. Fails with the asset listed in more than one category.
+    pub fn asset_categories(&self) -> Result<BTreeMap<String, String>, String> {
+        let mut categories = BTreeMap::new();
+        for (category, assets) in self.classification() {
+            for asset in assets {
+                if categories.insert(asset.clone(), category.clone()).is_some() {
+                    return Err(asset);
+                }
+            }
+        }
+        Ok(categories)
+    }
+
+    /// Hex SHA-256 of the on-chain configs as JSON, so the token table and decimals the balances are read
+    /// with are committed. Chains, RPC URLs and tokens sorted, addresses lowercase and assets uppercase.
+    /// Empty without on-chain configs.
+    pub fn onchain_hash(&self) -> String {
+        if self.onchain.is_empty() {
+            return String::new();
+        }
+        let mut onchain = self.onchain.clone();
+        for chain in &mut onchain {
+            chain.rpc_urls.sort();
+            chain.native_asset = chain.native_asset.to_ascii_uppercase();
+            for token in &mut chain.tokens {
+                token.address = token.address.to_ascii_lowercase();
+                token.asset = token.asset.to_ascii_uppercase();
+            }
+            chain.tokens.sort_by(|a, b| a.address.cmp(&b.address));
+        }
+        onchain.sort_by(|a, b| a.chain.cmp(&b.chain));
+        hex::encode(Sha256::digest(serde_json::to_vec(&onchain).unwrap()))
+    }
+
+    /// Hex SHA-256 of the classification in use as JSON, categories and assets sorted.
+    pub fn classification_hash(&self) -> String {
+        hex::encode(Sha256::digest(serde_json::to_vec(&self.classification()).unwrap()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ThresholdStruct {
+    pub threshold: Decimal,
+    pub met: bool, // reserves >= threshold
 }
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
@@ -16,6 +244,116 @@ pub struct PublicValuesStruct {
     pub version: String,
     pub project_id: String,
     pub attestation_meta: Vec<AttestationMetaStruct>,
-    pub asset_balance: HashMap<String, HashMap<String, f64>>, // source => { asset => balance }
+    pub asset_balance: BTreeMap<String, BTreeMap<String, Decimal>>, // source => { asset => balance }
+    pub block_numbers: BTreeMap<String, u64>,                       // chain => pinned block
+    pub onchain_hash: String,                                       // see `ProgramConfig::onchain_hash`
+    pub freshness: Option<FreshnessConfig>,                         // the bounds checked, None if unchecked
+    pub usd_value: BTreeMap<String, Decimal>, // asset => USD value over all sources, grouped like `asset_balance`
+    pub usd_total: Option<Decimal>,           // None without `valuation`
+    pub usd_price: BTreeMap<String, Decimal>, // asset => median USD price the values are taken at
+    pub thresholds: BTreeMap<String, ThresholdStruct>, // asset, or `USD` for `usd_total` => threshold
+    pub attestor_allowlist_hash: String,
+    pub min_attestors: u64, // m of the m-of-n attestor check
+    pub classification_hash: String,
+    pub disclosure_scope: String,
+    pub detail_hash: String,  // hex SHA-256 of the salted detail, see `disclosure::Detail`
+    pub account_root: String, // hex of the Merkle root of the salted account commitments, see `por_lib::account`
+    pub account_count: u64,   // both empty without `accountSalt`
+    pub status: i16,
+    pub public_values_digest: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct LiabilityPublicValuesStruct {
+    pub kind: String,
+    pub version: String,
+    pub project_id: String,
+    pub liability_root: String, // hex of the Merkle sum tree root hash
+    pub user_count: u64,
+    pub liability_balance: BTreeMap<String, Decimal>, // asset => total liability
     pub status: i16,
+    pub public_values_digest: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SolvencyStruct {
+    pub reserve_ratio: Option<Decimal>, // reserves / liabilities, None if there are no liabilities
+    pub solvent: bool,                  // reserves >= liabilities
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SolvencyPublicValuesStruct {
+    pub kind: String,
+    pub version: String,
+    pub project_id: String,
+    pub attestation_meta: Vec<AttestationMetaStruct>,
+    pub liability_root: String, // hex of the Merkle sum tree root hash
+    pub user_count: u64,
+    pub solvency: BTreeMap<String, SolvencyStruct>,   // asset => solvency
+    pub reserve_balance: BTreeMap<String, Decimal>,   // asset => reserves, empty unless `reveal_amounts`
+    pub liability_balance: BTreeMap<String, Decimal>, // asset => liabilities, empty unless `reveal_amounts`
+    pub block_numbers: BTreeMap<String, u64>,         // chain => pinned block
+    pub onchain_hash: String,                         // see `ProgramConfig::onchain_hash`
+    pub freshness: Option<FreshnessConfig>,           // the bounds checked, None if unchecked
+    pub attestor_allowlist_hash: String,
+    pub min_attestors: u64, // m of the m-of-n attestor check
+    pub status: i16,
+    pub public_values_digest: String,
+}
+
+/// Handling shared by the public values of every `ProofKind`.
+pub trait PublicValues: Serialize + Clone + Default {
+    fn set_status(&mut self, status: i16);
+
+    fn set_digest(&mut self, digest: String);
+
+    /// The Solidity ABI encoding, see `abi`. Fails on an amount the ABI cannot carry.
+    fn abi_encode(&self) -> Result<Vec<u8>, ZktlsError>;
+
+    /// Hex SHA-256 of the canonical JSON encoding, taken with `public_values_digest` left empty.
+    ///
+    /// The encoding is canonical: fields are in declaration order, map keys are sorted
+    /// and balances are normalized decimal strings.
+    fn digest(&self) -> String {
+        let mut pv = self.clone();
+        pv.set_digest(String::new());
+        let bytes = serde_json::to_vec(&pv).unwrap();
+        hex::encode(Sha256::digest(&bytes))
+    }
+}
+
+impl PublicValues for PublicValuesStruct {
+    fn set_status(&mut self, status: i16) {
+        self.status = status;
+    }
+    fn set_digest(&mut self, digest: String) {
+        self.public_values_digest = digest;
+    }
+    fn abi_encode(&self) -> Result<Vec<u8>, ZktlsError> {
+        crate::abi::encode_public_values(self)
+    }
+}
+
+impl PublicValues for LiabilityPublicValuesStruct {
+    fn set_status(&mut self, status: i16) {
+        self.status = status;
+    }
+    fn set_digest(&mut self, digest: String) {
+        self.public_values_digest = digest;
+    }
+    fn abi_encode(&self) -> Result<Vec<u8>, ZktlsError> {
+        crate::abi::encode_liability_public_values(self)
+    }
+}
+
+impl PublicValues for SolvencyPublicValuesStruct {
+    fn set_status(&mut self, status: i16) {
+        self.status = status;
+    }
+    fn set_digest(&mut self, digest: String) {
+        self.public_values_digest = digest;
+    }
+    fn abi_encode(&self) -> Result<Vec<u8>, ZktlsError> {
+        crate::abi::encode_solvency_public_values(self)
+    }
 }
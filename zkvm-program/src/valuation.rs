@@ -0,0 +1,119 @@
+use por_lib::decimal::Decimal;
+use por_lib::price::{median_price, PriceError};
+use std::collections::{BTreeMap, HashMap};
+use zktls_att_verification::attestation_data::AttestationConfig;
+
+use crate::errors::{ZkErrorCode, ZktlsError};
+use crate::sources::{verify_source, PRICE_SOURCES};
+use crate::structs::{AttestationMetaStruct, ProgramConfig, ValuationConfig};
+use crate::{ensure_zk, zkerr};
+
+/// Fractional digits of the committed USD values.
+const USD_SCALE: u32 = 2;
+
+/// Verifies the price attestation of every venue present, returning symbol => prices, one per venue.
+///
+/// The prices must be taken in the time window of the balances: `freshness` must bound the age or the spread
+/// of all attestations, the prices included.
+pub fn verify_prices(
+    attestation_meta: &mut Vec<AttestationMetaStruct>,
+    attestations: &HashMap<String, String>,
+    attestation_config: &AttestationConfig,
+    program_config: &ProgramConfig,
+) -> Result<BTreeMap<String, Vec<Decimal>>, ZktlsError> {
+    ensure_zk!(
+        program_config
+            .freshness
+            .as_ref()
+            .is_some_and(|f| f.max_age.is_some() || f.max_spread.is_some()),
+        zkerr!(
+            ZkErrorCode::MissingFreshness,
+            "valuation requires maxAge or maxSpread".to_string()
+        )
+    );
+    let mut prices: BTreeMap<String, Vec<Decimal>> = BTreeMap::new();
+    for source in PRICE_SOURCES {
+        let Some(data) = attestations.get(source.key()) else {
+            continue;
+        };
+        let mut am = AttestationMetaStruct::default();
+        let mut venue_prices: BTreeMap<String, Decimal> = BTreeMap::new();
+        verify_source(
+            *source,
+            &mut am,
+            data,
+            attestation_config,
+            program_config,
+            &mut venue_prices,
+            None,
+        )?;
+        attestation_meta.push(am);
+
+        for (symbol, price) in venue_prices {
+            prices.entry(symbol).or_default().push(price);
+        }
+    }
+    ensure_zk!(!prices.is_empty(), zkerr!(ZkErrorCode::MissingPriceData));
+
+    Ok(prices)
+}
+
+impl From<PriceError> for ZktlsError {
+    fn from(e: PriceError) -> Self {
+        let code = match e {
+            PriceError::MissingPriceData(_) => ZkErrorCode::MissingPriceData,
+            PriceError::PriceDeviation(_) => ZkErrorCode::PriceDeviation,
+            PriceError::BalanceOverflow(_) => ZkErrorCode::BalanceOverflow,
+        };
+        ZktlsError::new(code, e.to_string())
+    }
+}
+
+/// asset => USD price of the assets held in `reserves`. Fails on an asset without a price.
+pub fn usd_prices(
+    reserves: &BTreeMap<String, Decimal>,
+    prices: &BTreeMap<String, Vec<Decimal>>,
+    valuation: &ValuationConfig,
+) -> Result<BTreeMap<String, Decimal>, ZktlsError> {
+    let quote = valuation.quote.to_ascii_uppercase();
+    let mut usd_prices = BTreeMap::new();
+    for (asset, bal) in reserves {
+        if *bal == Decimal::ZERO {
+            continue;
+        }
+        let pegged = if valuation.pegged.is_empty() {
+            *asset == quote
+        } else {
+            valuation.pegged.iter().any(|a| a.eq_ignore_ascii_case(asset))
+        };
+        let price = if pegged {
+            Decimal::from_scaled(1, 0)
+        } else {
+            median_price(
+                &format!("{}{}", asset, quote),
+                prices,
+                valuation.min_price_venues,
+                &valuation.max_price_deviation,
+            )?
+        };
+        usd_prices.insert(asset.clone(), price);
+    }
+    Ok(usd_prices)
+}
+
+/// asset => USD value of `reserves`, truncated to cents. Assets not held have no price and no value.
+pub fn usd_values(
+    reserves: &BTreeMap<String, Decimal>,
+    usd_prices: &BTreeMap<String, Decimal>,
+) -> Result<BTreeMap<String, Decimal>, ZktlsError> {
+    let mut values = BTreeMap::new();
+    for (asset, bal) in reserves {
+        if let Some(price) = usd_prices.get(asset) {
+            let value = bal
+                .checked_mul(price, USD_SCALE)
+                .ok_or_else(|| zkerr!(ZkErrorCode::BalanceOverflow, asset.clone()))?;
+            values.insert(asset.clone(), value);
+        }
+    }
+    Ok(values)
+}